@@ -1,7 +1,13 @@
+use depot_js::commands::run::ProgramFailed;
+
 #[tokio::main]
 async fn main() {
   env_logger::init();
   if let Err(e) = depot_js::run().await {
+    // The program run by `depot run` has already reported its own failure.
+    if let Some(failed) = e.downcast_ref::<ProgramFailed>() {
+      std::process::exit(failed.code());
+    }
     eprintln!("Depot failed with the error: {e:?}");
     std::process::exit(1);
   }
@@ -0,0 +1,287 @@
+//! Bundling packages with esbuild. Scripts are written to the same files in `dist` as a
+//! script built with Vite library mode. Sites are bundled from the entries referenced by
+//! `index.html`, which is rewritten into `dist` next to them along with `public`.
+
+use std::{
+  fs,
+  path::{Path, PathBuf},
+  sync::LazyLock,
+};
+
+use anyhow::{Context, Result, ensure};
+use regex::{Captures, Regex};
+
+use crate::{
+  utils,
+  workspace::{package::Package, profile::Profile},
+};
+
+/// A root-relative `src` or `href` attribute in `index.html`, like Vite resolves them.
+static HTML_REFERENCE: LazyLock<Regex> =
+  LazyLock::new(|| Regex::new(r#"\b(src|href)="/([^"]+)""#).unwrap());
+
+/// Extensions of site entries, which esbuild compiles to the given extension.
+const SITE_ENTRY_EXTENSIONS: &[(&str, &str)] = &[
+  ("ts", "js"),
+  ("tsx", "js"),
+  ("mts", "js"),
+  ("js", "js"),
+  ("jsx", "js"),
+  ("mjs", "js"),
+  ("css", "css"),
+];
+
+/// Extensions of files imported by a site, which are copied into `dist/assets`.
+const SITE_ASSET_EXTENSIONS: &[&str] = &[
+  "png", "jpg", "jpeg", "gif", "svg", "webp", "woff", "woff2", "ttf", "wasm",
+];
+
+/// The file written to `dist`, named like Vite names the output of library mode.
+fn out_file(pkg: &Package) -> String {
+  if pkg.platform.is_node() {
+    format!("dist/{}.cjs", pkg.name.name)
+  } else {
+    format!("dist/{}.iife.js", pkg.name.name)
+  }
+}
+
+/// The output path of a site entry relative to `dist`, or `None` if `path` is not an entry.
+fn site_output(pkg: &Package, path: &str) -> Option<PathBuf> {
+  let path = Path::new(path);
+  let ext = path.extension()?.to_str()?;
+  let (_, out_ext) = SITE_ENTRY_EXTENSIONS.iter().find(|(src, _)| *src == ext)?;
+  pkg
+    .root
+    .join(path)
+    .is_file()
+    .then(|| path.with_extension(out_ext))
+}
+
+fn site_entries(pkg: &Package) -> Result<Vec<String>> {
+  let html = fs::read_to_string(pkg.root.join("index.html"))
+    .with_context(|| format!("Could not read `index.html` of `{}`", pkg.name))?;
+  Ok(
+    HTML_REFERENCE
+      .captures_iter(&html)
+      .map(|captures| captures[2].to_string())
+      .filter(|path| site_output(pkg, path).is_some())
+      .collect(),
+  )
+}
+
+pub fn args(pkg: &Package, profile: &Profile, watch: bool) -> Result<Vec<String>> {
+  let mut args = if pkg.target.is_site() {
+    site_args(pkg, watch)?
+  } else {
+    script_args(pkg)?
+  };
+
+  let node_env = serde_json::Value::String(profile.mode.clone());
+  args.push(format!("--define:process.env.NODE_ENV={node_env}"));
+  args.extend(
+    profile
+      .define
+      .iter()
+      .map(|(key, value)| format!("--define:{key}={value}")),
+  );
+
+  if profile.sourcemap {
+    args.push("--sourcemap".into());
+  }
+  if profile.minify {
+    args.push("--minify".into());
+  }
+  if watch {
+    // Otherwise esbuild stops watching when its stdin is closed.
+    args.push("--watch=forever".into());
+  }
+
+  Ok(args)
+}
+
+fn script_args(pkg: &Package) -> Result<Vec<String>> {
+  let entry = pkg
+    .entry_point()
+    .with_context(|| format!("Could not find entry point `src/main` of `{}`", pkg.name))?;
+  let entry = entry.strip_prefix(&pkg.root).unwrap_or(&entry);
+
+  let mut args = vec![
+    entry.display().to_string(),
+    "--bundle".into(),
+    "--log-level=info".into(),
+    "--color=true".into(),
+    format!("--outfile={}", out_file(pkg)),
+  ];
+
+  // The node platform also marks builtin modules as external.
+  if pkg.platform.is_node() {
+    args.extend(["--platform=node".into(), "--format=cjs".into()]);
+  } else {
+    args.extend([
+      "--platform=browser".into(),
+      "--format=iife".into(),
+      format!("--global-name={}", pkg.name.as_global_var()),
+    ]);
+  }
+
+  if let Some(deps) = &pkg.manifest.manifest.dependencies {
+    args.extend(deps.keys().map(|dep| format!("--external:{dep}")));
+  }
+
+  Ok(args)
+}
+
+fn site_args(pkg: &Package, watch: bool) -> Result<Vec<String>> {
+  let entries = site_entries(pkg)?;
+  ensure!(
+    !entries.is_empty(),
+    "`index.html` of `{}` does not reference any scripts or stylesheets",
+    pkg.name
+  );
+
+  // Unlike scripts, sites bundle their dependencies, since they run in the browser.
+  let mut args = entries;
+  args.extend([
+    "--bundle".into(),
+    "--log-level=info".into(),
+    "--color=true".into(),
+    "--outdir=dist".into(),
+    "--outbase=.".into(),
+    "--platform=browser".into(),
+    "--format=esm".into(),
+    "--asset-names=assets/[name]-[hash]".into(),
+  ]);
+  args.extend(
+    SITE_ASSET_EXTENSIONS
+      .iter()
+      .map(|ext| format!("--loader:.{ext}=file")),
+  );
+
+  let no_server = pkg.manifest.config.no_server.unwrap_or(false);
+  if watch && !no_server {
+    args.extend(["--serve".into(), "--servedir=dist".into()]);
+  }
+
+  Ok(args)
+}
+
+/// Writes `index.html` to `dist` with its entries pointing at their outputs, and copies
+/// the files in `public` to `dist`.
+pub fn write_site_files(pkg: &Package) -> Result<()> {
+  let dist = pkg.root.join("dist");
+  utils::create_dir_if_missing(&dist)?;
+
+  let html = fs::read_to_string(pkg.root.join("index.html"))
+    .with_context(|| format!("Could not read `index.html` of `{}`", pkg.name))?;
+  let html = HTML_REFERENCE.replace_all(&html, |captures: &Captures| {
+    let (attr, path) = (&captures[1], &captures[2]);
+    match site_output(pkg, path) {
+      Some(output) => format!("{attr}=\"./{}\"", output.display()),
+      None => captures[0].to_string(),
+    }
+  });
+  fs::write(dist.join("index.html"), html.as_ref())?;
+
+  let public = pkg.root.join("public");
+  if public.is_dir() {
+    for file in super::files_under(&public) {
+      let target_path = dist.join(file.strip_prefix(&public)?);
+      utils::create_dir_if_missing(target_path.parent().unwrap())?;
+      fs::copy(&file, target_path)?;
+    }
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use std::fs;
+
+  use indexmap::IndexMap;
+
+  use super::*;
+  use crate::{
+    utils,
+    workspace::package::{PackageManifest, Target},
+  };
+
+  #[test]
+  fn test_esbuild_args() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().to_path_buf();
+    utils::create_dir_if_missing(root.join("src"))?;
+    fs::write(root.join("src/main.ts"), "")?;
+    let manifest = PackageManifest::from_json(
+      serde_json::from_value(serde_json::json!({
+        "name": "my-cli",
+        "dependencies": {"chalk": "^5"},
+        "depot": {"platform": "node", "bundler": "esbuild"}
+      }))?,
+      &root.join("package.json"),
+    )?;
+    let pkg = Package::from_parts(root.clone(), manifest, 0, Target::Script)?;
+    let profile = Profile::resolve("release", &IndexMap::new())?;
+
+    let args = args(&pkg, &profile, false)?;
+    assert_eq!(args[0], "src/main.ts");
+    for arg in [
+      "--outfile=dist/my-cli.cjs",
+      "--format=cjs",
+      "--external:chalk",
+      "--define:process.env.NODE_ENV=\"production\"",
+      "--minify",
+    ] {
+      assert!(args.iter().any(|a| a == arg), "missing {arg} in {args:?}");
+    }
+    assert!(!args.iter().any(|a| a.starts_with("--watch")));
+    Ok(())
+  }
+
+  #[test]
+  fn test_esbuild_site() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().to_path_buf();
+    utils::create_dir_if_missing(root.join("src"))?;
+    utils::create_dir_if_missing(root.join("styles"))?;
+    utils::create_dir_if_missing(root.join("public/img"))?;
+    fs::write(root.join("src/index.tsx"), "")?;
+    fs::write(root.join("styles/index.css"), "")?;
+    fs::write(root.join("public/img/logo.png"), "")?;
+    fs::write(
+      root.join("index.html"),
+      r#"<link rel="icon" href="/favicon.ico" />
+<link rel="stylesheet" href="/styles/index.css" />
+<script type="module" src="/src/index.tsx"></script>"#,
+    )?;
+    let manifest = PackageManifest::from_json(
+      serde_json::from_value(serde_json::json!({
+        "name": "my-site",
+        "depot": {"platform": "browser", "bundler": "esbuild"}
+      }))?,
+      &root.join("package.json"),
+    )?;
+    let pkg = Package::from_parts(root.clone(), manifest, 0, Target::Site)?;
+    let profile = Profile::resolve("dev", &IndexMap::new())?;
+
+    let args = args(&pkg, &profile, true)?;
+    assert_eq!(args[..2], ["styles/index.css", "src/index.tsx"]);
+    for arg in [
+      "--outdir=dist",
+      "--format=esm",
+      "--servedir=dist",
+      "--watch=forever",
+    ] {
+      assert!(args.iter().any(|a| a == arg), "missing {arg} in {args:?}");
+    }
+
+    write_site_files(&pkg)?;
+    let html = fs::read_to_string(root.join("dist/index.html"))?;
+    assert!(html.contains(r#"href="/favicon.ico""#), "{html}");
+    assert!(html.contains(r#"href="./styles/index.css""#), "{html}");
+    assert!(html.contains(r#"src="./src/index.js""#), "{html}");
+    assert!(root.join("dist/img/logo.png").exists());
+
+    Ok(())
+  }
+}
@@ -0,0 +1,614 @@
+use std::{
+  collections::HashSet,
+  fs,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+
+use anyhow::{Result, anyhow, ensure};
+use futures::{
+  FutureExt,
+  future::{try_join, try_join_all},
+};
+use ignore::WalkBuilder;
+use log::debug;
+use notify::RecursiveMode;
+
+use self::rewrite::ImportRewriter;
+use super::init::{InitArgs, InitCommand};
+use crate::{
+  utils,
+  workspace::{
+    Command, CommandRuntime, CoreCommand, PackageCommand,
+    linter::Invocation,
+    package::{Bundler, Package, Target},
+  },
+};
+
+mod esbuild;
+mod package_check;
+mod rewrite;
+mod script;
+
+/// Check and build packages
+#[derive(clap::Parser, Default, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct BuildArgs {
+  /// Build in release mode, same as `--profile release`
+  #[arg(short, long)]
+  pub release: bool,
+
+  /// Rebuild when files change
+  #[clap(short, long, action)]
+  pub watch: bool,
+
+  /// Fail if the linter finds an issue
+  #[clap(short, long, action)]
+  pub lint_fail: bool,
+}
+
+#[derive(Debug)]
+pub struct BuildCommand {
+  args: BuildArgs,
+}
+
+const BUILD_SCRIPT: &str = "build.mjs";
+
+const VITE_CONFIGS: &[&str] = &[
+  "vite.config.ts",
+  "vite.config.mts",
+  "vite.config.js",
+  "vite.config.mjs",
+];
+
+/// Asset extensions that are compiled to CSS by `sass` rather than copied.
+const SASS_EXTENSIONS: &[&str] = &["scss", "sass"];
+
+fn is_sass(path: &Path) -> bool {
+  path
+    .extension()
+    .and_then(|ext| ext.to_str())
+    .is_some_and(|ext| SASS_EXTENSIONS.contains(&ext))
+}
+
+impl CoreCommand for BuildCommand {
+  fn name(&self) -> String {
+    "build".into()
+  }
+}
+
+#[async_trait::async_trait]
+impl PackageCommand for BuildCommand {
+  async fn run_pkg(&self, pkg: &Package) -> Result<()> {
+    if pkg.root.join(BUILD_SCRIPT).exists() {
+      self.build_script(pkg).await?;
+    }
+
+    let compile_sass = Self::compiles_sass(pkg);
+    // In watch mode, the asset watcher starts `sass` once a Sass source is added.
+    let run_sass = compile_sass && pkg.asset_files().any(|file| is_sass(&file));
+    let mut processes = Vec::new();
+
+    processes.push(match pkg.target {
+      Target::Site => {
+        if pkg.uses_vike() {
+          self.vike(pkg).boxed()
+        } else {
+          match pkg.bundler() {
+            Bundler::Vite => self.vite(pkg).boxed(),
+            Bundler::Esbuild => self.esbuild(pkg).boxed(),
+          }
+        }
+      }
+      Target::Script => match pkg.bundler() {
+        Bundler::Vite => self.vite(pkg).boxed(),
+        Bundler::Esbuild => self.esbuild(pkg).boxed(),
+      },
+      Target::Lib if pkg.is_bundled() => self.vite(pkg).boxed(),
+      Target::Lib => self.copy_assets(pkg, compile_sass, run_sass).boxed(),
+    });
+
+    if run_sass {
+      processes.push(self.sass(pkg).boxed());
+    }
+
+    processes.extend([self.tsc(pkg, compile_sass).boxed(), self.lint(pkg).boxed()]);
+
+    // In watch mode the build never finishes, so check the package after each rebuild.
+    let watch_package = pkg.target.is_lib() && self.args.watch;
+    if watch_package {
+      processes.push(Self::watch_package(pkg).boxed());
+    }
+
+    try_join_all(processes).await?;
+
+    if pkg.target.is_lib() && !watch_package {
+      self.check_package(pkg)?;
+    }
+
+    Ok(())
+  }
+
+  fn deps(&self) -> Vec<Command> {
+    vec![InitCommand::new(InitArgs::default()).kind()]
+  }
+
+  fn runtime(&self) -> CommandRuntime {
+    if self.args.watch {
+      CommandRuntime::RunForever
+    } else {
+      CommandRuntime::WaitForDependencies
+    }
+  }
+}
+
+impl BuildCommand {
+  pub fn new(args: BuildArgs) -> Self {
+    BuildCommand { args }
+  }
+
+  pub fn kind(self) -> Command {
+    Command::package(self)
+  }
+
+  /// Runs a process to completion, or in watch mode, keeps it running across crashes.
+  async fn exec(
+    &self,
+    pkg: &Package,
+    script: &'static str,
+    configure: impl FnOnce(&mut tokio::process::Command),
+  ) -> Result<()> {
+    let process = pkg.start_process(script, configure)?;
+    if self.args.watch {
+      process.supervise(pkg.workspace().max_restarts()).await
+    } else {
+      process.wait_for_success().await
+    }
+  }
+
+  async fn tsc(&self, pkg: &Package, compile_sass: bool) -> Result<()> {
+    let tsc = self.exec(pkg, "tsc", |cmd| {
+      cmd.arg("--pretty");
+      if self.args.watch {
+        cmd.arg("--watch");
+      }
+      if pkg.is_bundled() {
+        // Vite emits the library and its declarations.
+        cmd.arg("--noEmit");
+      } else if pkg.target.is_lib() && pkg.workspace().profile.sourcemap {
+        cmd.arg("--sourceMap");
+      }
+    });
+
+    if !pkg.target.is_lib() || pkg.is_bundled() {
+      return tsc.await;
+    }
+
+    let rewriter = ImportRewriter::new(pkg, compile_sass)?;
+    if self.args.watch {
+      try_join(tsc, rewriter.watch()).await?;
+    } else {
+      tsc.await?;
+      rewriter.rewrite_all()?;
+    }
+    Ok(())
+  }
+
+  async fn lint(&self, pkg: &Package) -> Result<()> {
+    let steps = pkg.linter().check();
+    try_join_all(steps.iter().map(|step| self.lint_step(pkg, *step))).await?;
+    Ok(())
+  }
+
+  /// Runs one of the linter's checks, and again whenever a source file changes.
+  async fn lint_step(&self, pkg: &Package, (program, args): Invocation) -> Result<()> {
+    let configure = |cmd: &mut tokio::process::Command| {
+      cmd.args(args);
+      cmd.args(pkg.source_files());
+    };
+    let process = pkg.start_process(program, configure)?;
+
+    let status = process.wait().await?;
+    if !self.args.watch {
+      ensure!(!self.args.lint_fail || status.success(), "{program} failed");
+      return Ok(());
+    }
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let timeout = Duration::from_secs(1);
+    let mut debouncer = notify_debouncer_mini::new_debouncer(timeout, move |events| {
+      let _ = tx.send(events);
+    })?;
+
+    // Watch directories rather than files so that new source files are linted too.
+    let watcher = debouncer.watcher();
+    watcher.watch(&pkg.root, RecursiveMode::NonRecursive)?;
+    for dir in ["src", "tests"] {
+      let dir = pkg.root.join(dir);
+      if dir.exists() {
+        watcher.watch(&dir, RecursiveMode::Recursive)?;
+      }
+    }
+
+    let source_files =
+      || -> HashSet<PathBuf> { pkg.source_files().map(|path| pkg.root.join(path)).collect() };
+    let mut sources = source_files();
+    while let Some(events) = rx.recv().await {
+      let events = events.map_err(|e| anyhow!("File watch errors: {e:?}"))?;
+      let new_sources = source_files();
+      let changed = events
+        .iter()
+        .any(|event| sources.contains(&event.path) || new_sources.contains(&event.path));
+      sources = new_sources;
+      // Skip if the process was restarted from the UI and is already running.
+      if changed && process.finished() {
+        process.respawn(pkg.command(program, configure)?)?;
+        process.wait().await?;
+      }
+    }
+
+    Ok(())
+  }
+
+  /// Reports package.json entry points that don't match the built files.
+  fn check_package(&self, pkg: &Package) -> Result<()> {
+    let problems = package_check::check_package(pkg)?;
+    let log = pkg.log_process("package.json");
+    for problem in &problems {
+      log.push_line(format!("\u{1b}[33mwarning\u{1b}[0m: {problem}"));
+    }
+    // Problems are only warnings unless they fail the build.
+    let failed = self.args.lint_fail && !problems.is_empty();
+    log.finish(!failed);
+    ensure!(!failed, "package.json has {} problem(s)", problems.len());
+    Ok(())
+  }
+
+  /// Checks package.json whenever the files in `dist` change, logging the problems
+  /// when they differ from the last check. Problems don't fail the build, since it
+  /// keeps watching.
+  async fn watch_package(pkg: &Package) -> Result<()> {
+    let dist = pkg.root.join("dist");
+    utils::create_dir_if_missing(&dist)?;
+    let log = pkg.log_process("package.json");
+    log.push_line("Checking package.json after each build");
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let timeout = Duration::from_secs(1);
+    let mut debouncer = notify_debouncer_mini::new_debouncer(timeout, move |events| {
+      let _ = tx.send(events);
+    })?;
+    debouncer.watcher().watch(&dist, RecursiveMode::Recursive)?;
+
+    let mut last_problems = None;
+    while let Some(events) = rx.recv().await {
+      events.map_err(|e| anyhow!("File watch errors: {e:?}"))?;
+      let problems = package_check::check_package(pkg)?
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+      if last_problems.as_ref() == Some(&problems) {
+        continue;
+      }
+      if problems.is_empty() {
+        log.push_line("package.json has no problems");
+      }
+      for problem in &problems {
+        log.push_line(format!("\u{1b}[33mwarning\u{1b}[0m: {problem}"));
+      }
+      last_problems = Some(problems);
+    }
+
+    Ok(())
+  }
+
+  /// Whether an unbundled library compiles its Sass sources with `sass`, rather than
+  /// copying them.
+  fn compiles_sass(pkg: &Package) -> bool {
+    if !pkg.target.is_lib() || pkg.is_bundled() {
+      return false;
+    }
+    let has_sass = pkg.asset_files().any(|file| is_sass(&file));
+    if pkg.find_bin("sass").is_some() {
+      return true;
+    }
+    if has_sass {
+      let log = pkg.log_process("sass");
+      log.push_line(
+        "\u{1b}[33mwarning\u{1b}[0m: `sass` is not installed, so Sass files are copied without being compiled. Add `sass` to devDependencies to compile them.",
+      );
+      log.finish(true);
+    }
+    false
+  }
+
+  async fn sass(&self, pkg: &Package) -> Result<()> {
+    self
+      .exec(pkg, "sass", |cmd| {
+        cmd.arg("src:dist");
+        let profile = &pkg.workspace().profile;
+        if profile.sourcemap {
+          cmd.arg("--source-map");
+        } else {
+          cmd.arg("--no-source-map");
+        }
+        if profile.minify {
+          cmd.arg("--style=compressed");
+        }
+        if self.args.watch {
+          cmd.arg("--watch");
+        }
+      })
+      .await
+  }
+
+  /// Warns if the profile sets `define` but the package's Vite config doesn't read it.
+  /// Only configs generated by `depot new` read `DEPOT_DEFINE`.
+  fn check_define(pkg: &Package) {
+    if pkg.workspace().profile.define.is_empty() {
+      return;
+    }
+    let reads_define = VITE_CONFIGS
+      .iter()
+      .filter_map(|name| fs::read_to_string(pkg.root.join(name)).ok())
+      .any(|config| config.contains("DEPOT_DEFINE"));
+    if !reads_define {
+      let log = pkg.log_process("define");
+      log.push_line(
+        "\u{1b}[33mwarning\u{1b}[0m: The profile sets `define`, but the Vite config doesn't read `process.env.DEPOT_DEFINE`, so it is ignored. See the README section on profiles.",
+      );
+      log.finish(true);
+    }
+  }
+
+  async fn vike(&self, pkg: &Package) -> Result<()> {
+    Self::check_define(pkg);
+    let no_server = pkg.manifest.config.no_server.unwrap_or(false);
+    if self.args.watch && !no_server {
+      self
+        .exec(pkg, "vike", |cmd| {
+          cmd.env("FORCE_COLOR", "1");
+          cmd.arg("dev");
+        })
+        .await
+    } else {
+      pkg
+        .exec("vike", |cmd| {
+          cmd.env("FORCE_COLOR", "1");
+          cmd.arg("build");
+        })
+        .await
+    }
+  }
+
+  async fn vite(&self, pkg: &Package) -> Result<()> {
+    Self::check_define(pkg);
+    let profile = &pkg.workspace().profile;
+    self
+      .exec(pkg, "vite", |cmd| {
+        cmd.env("FORCE_COLOR", "1");
+        let no_server = pkg.manifest.config.no_server.unwrap_or(false);
+        if pkg.target.is_site() && self.args.watch && !no_server {
+          cmd.arg("dev");
+        } else {
+          cmd.arg("build");
+          if self.args.watch {
+            cmd.arg("--watch");
+          }
+          if profile.sourcemap {
+            cmd.args(["--sourcemap", "true"]);
+          }
+          if profile.minify {
+            cmd.arg("--minify");
+          } else {
+            cmd.args(["--minify", "false"]);
+          }
+        }
+        cmd.args(["--mode", &profile.mode]);
+      })
+      .await
+  }
+
+  async fn esbuild(&self, pkg: &Package) -> Result<()> {
+    let args = esbuild::args(pkg, &pkg.workspace().profile, self.args.watch)?;
+    let bundle = self.exec(pkg, "esbuild", |cmd| {
+      cmd.args(args);
+    });
+
+    if !pkg.target.is_site() {
+      return bundle.await;
+    }
+
+    esbuild::write_site_files(pkg)?;
+    if self.args.watch {
+      try_join(bundle, Self::watch_site_files(pkg)).await?;
+      Ok(())
+    } else {
+      bundle.await
+    }
+  }
+
+  /// Rewrites the site files in `dist` when `index.html` or `public` changes, since
+  /// esbuild only watches the entries it bundles.
+  async fn watch_site_files(pkg: &Package) -> Result<()> {
+    let log = pkg.log_process("site");
+    let public = pkg.root.join("public");
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let timeout = Duration::from_secs(1);
+    let mut debouncer = notify_debouncer_mini::new_debouncer(timeout, move |events| {
+      let _ = tx.send(events);
+    })?;
+    debouncer
+      .watcher()
+      .watch(&pkg.root, RecursiveMode::NonRecursive)?;
+    if public.is_dir() {
+      debouncer
+        .watcher()
+        .watch(&public, RecursiveMode::Recursive)?;
+    }
+
+    while let Some(events) = rx.recv().await {
+      let events = events.map_err(|e| anyhow!("File watch errors: {e:?}"))?;
+      let changed = events
+        .iter()
+        .any(|event| event.path.ends_with("index.html") || event.path.starts_with(&public));
+      if changed {
+        match esbuild::write_site_files(pkg) {
+          Ok(()) => log.push_line("Updated index.html and public files"),
+          Err(e) => log.push_line(format!("Failed to update site files: {e:?}")),
+        }
+      }
+    }
+
+    Ok(())
+  }
+
+  async fn build_script(&self, pkg: &Package) -> Result<()> {
+    // The context file must outlive the script, so it is held until the script exits.
+    let context = script::write_context(pkg, self.args.watch)?;
+    let diagnostics = script::Diagnostics::default();
+    let process = pkg.start_filtered_process("node", Some(diagnostics.filter()), |cmd| {
+      cmd.arg(BUILD_SCRIPT);
+      cmd.env(script::CONTEXT_VAR, context.path());
+      if self.args.watch {
+        cmd.arg("--watch");
+      }
+      if pkg.workspace().profile.is_production() {
+        cmd.arg("--release");
+      }
+    })?;
+
+    // A script that builds once and exits is done, even in watch mode.
+    let result = if self.args.watch {
+      process.retry(pkg.workspace().max_restarts()).await
+    } else {
+      process.wait_for_success().await
+    };
+    result?;
+
+    let errors = diagnostics.errors();
+    ensure!(errors == 0, "{BUILD_SCRIPT} reported {errors} error(s)");
+    Ok(())
+  }
+
+  async fn copy_assets(&self, pkg: &Package, compile_sass: bool, run_sass: bool) -> Result<()> {
+    let src_dir = pkg.root.join("src");
+    let dst_dir = pkg.root.join("dist");
+
+    let copy = |file: &Path| -> Result<()> {
+      let rel_path = file.strip_prefix(&src_dir)?;
+      let target_path = dst_dir.join(rel_path);
+      utils::create_dir_if_missing(target_path.parent().unwrap())?;
+      debug!("copying: {} -> {}", file.display(), target_path.display());
+      fs::copy(file, target_path)?;
+      Ok(())
+    };
+
+    let copied = |file: &Path| pkg.is_asset(file) && !(compile_sass && is_sass(file));
+    for file in pkg.asset_files().filter(|file| copied(file)) {
+      copy(&file)?;
+    }
+
+    if self.args.watch {
+      let log = pkg.log_process("assets");
+      log.push_line(format!("Watching {} for assets", src_dir.display()));
+
+      let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+      let timeout = Duration::from_secs(1);
+      let mut debouncer = notify_debouncer_mini::new_debouncer(timeout, move |events| {
+        let _ = tx.send(events);
+      })?;
+      debouncer
+        .watcher()
+        .watch(&src_dir, RecursiveMode::Recursive)?;
+
+      // `sass --watch` started here once the first Sass source is added.
+      let mut sass = None;
+
+      // The debouncer doesn't say what kind of change happened, so mirror whatever is
+      // on disk now. A rename shows up as a deletion of the old path and a new path.
+      loop {
+        let events = tokio::select! {
+          events = rx.recv() => match events {
+            Some(events) => events,
+            None => break,
+          },
+          result = async { sass.as_mut().unwrap().await }, if sass.is_some() => {
+            sass = None;
+            result?;
+            continue;
+          }
+        };
+        let events = events.map_err(|e| anyhow!("File watch errors: {e:?}"))?;
+        for event in events {
+          let path = &event.path;
+          let rel_path = path.strip_prefix(&src_dir)?;
+          // Sass sources are compiled by `sass --watch`, but it leaves the output of
+          // deleted sources behind.
+          if compile_sass && is_sass(path) {
+            if !path.exists() {
+              let mut css = dst_dir.join(rel_path);
+              css.set_extension("css");
+              let mut map = css.clone().into_os_string();
+              map.push(".map");
+              for target_path in [css, PathBuf::from(map)] {
+                if target_path.exists() {
+                  fs::remove_file(&target_path)?;
+                  log.push_line(format!(
+                    "Removed {}",
+                    target_path.strip_prefix(&dst_dir)?.display()
+                  ));
+                }
+              }
+            } else if !run_sass && sass.is_none() {
+              log.push_line(format!(
+                "Found Sass source {}, starting sass",
+                rel_path.display()
+              ));
+              sass = Some(self.sass(pkg).boxed());
+            }
+            continue;
+          }
+
+          if path.is_file() {
+            if pkg.is_asset(path) {
+              copy(path)?;
+              log.push_line(format!("Copied {}", rel_path.display()));
+            }
+          } else if path.is_dir() {
+            for file in files_under(path) {
+              if copied(&file) {
+                copy(&file)?;
+                log.push_line(format!("Copied {}", file.strip_prefix(&src_dir)?.display()));
+              }
+            }
+          } else {
+            // Only remove assets, since `dist` also holds compiled sources.
+            for target_path in files_under(&dst_dir.join(rel_path)) {
+              let rel_path = target_path.strip_prefix(&dst_dir)?;
+              if pkg.is_asset(&src_dir.join(rel_path)) {
+                fs::remove_file(&target_path)?;
+                log.push_line(format!("Removed {}", rel_path.display()));
+              }
+            }
+          }
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// All files in `path` if it is a directory, or `path` itself if it is a file.
+fn files_under(path: &Path) -> Vec<PathBuf> {
+  WalkBuilder::new(path)
+    .standard_filters(false)
+    .build()
+    .filter_map(|entry| {
+      let entry = entry.ok()?;
+      entry.file_type()?.is_file().then(|| entry.into_path())
+    })
+    .collect()
+}
@@ -0,0 +1,269 @@
+//! Checks that a library's package.json points at files that exist after a build.
+
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use ignore::WalkBuilder;
+use serde_json::Value;
+
+use crate::workspace::package::Package;
+
+#[derive(PartialEq)]
+enum EntryKind {
+  Js,
+  Types,
+  Bin,
+}
+
+/// A path in package.json that must exist in a published package.
+struct Entry {
+  field: String,
+  path: String,
+  kind: EntryKind,
+}
+
+impl Entry {
+  fn new(field: impl Into<String>, path: &str, kind: EntryKind) -> Self {
+    Entry {
+      field: field.into(),
+      path: path.trim_start_matches("./").to_string(),
+      kind,
+    }
+  }
+}
+
+/// Collects the string targets of an `exports` value, whose keys are either subpaths
+/// or conditions.
+fn exports_entries(value: &Value, field: &str, entries: &mut Vec<Entry>) {
+  match value {
+    Value::String(path) => {
+      let kind = if field.ends_with("[\"types\"]") {
+        EntryKind::Types
+      } else {
+        EntryKind::Js
+      };
+      entries.push(Entry::new(field, path, kind));
+    }
+    Value::Object(map) => {
+      for (key, value) in map {
+        exports_entries(value, &format!("{field}[\"{key}\"]"), entries);
+      }
+    }
+    Value::Array(values) => {
+      for value in values {
+        exports_entries(value, field, entries);
+      }
+    }
+    _ => {}
+  }
+}
+
+fn manifest_entries(manifest: &Value) -> Vec<Entry> {
+  let mut entries = Vec::new();
+  if let Some(main) = manifest["main"].as_str() {
+    entries.push(Entry::new("main", main, EntryKind::Js));
+  }
+  for field in ["types", "typings"] {
+    if let Some(types) = manifest[field].as_str() {
+      entries.push(Entry::new(field, types, EntryKind::Types));
+    }
+  }
+  match &manifest["bin"] {
+    Value::String(path) => entries.push(Entry::new("bin", path, EntryKind::Bin)),
+    Value::Object(bins) => {
+      for (name, path) in bins {
+        if let Some(path) = path.as_str() {
+          entries.push(Entry::new(format!("bin.{name}"), path, EntryKind::Bin));
+        }
+      }
+    }
+    _ => {}
+  }
+  exports_entries(&manifest["exports"], "exports", &mut entries);
+  entries
+}
+
+/// Matches `path` against a glob-like `pattern` where `*` matches within a path
+/// component, and `**` (or any `*` if `cross_dirs`) matches across components.
+fn wildcard_match(pattern: &str, path: &str, cross_dirs: bool) -> bool {
+  match pattern.find('*') {
+    None => pattern == path,
+    Some(idx) => {
+      let (prefix, rest) = pattern.split_at(idx);
+      let Some(path) = path.strip_prefix(prefix) else {
+        return false;
+      };
+      let (cross_dirs, rest) = match rest.strip_prefix("**") {
+        Some(rest) => (true, rest.strip_prefix('/').unwrap_or(rest)),
+        None => (cross_dirs, &rest[1..]),
+      };
+      path
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain([path.len()])
+        .take_while(|i| cross_dirs || !path[..*i].contains('/'))
+        .any(|i| wildcard_match(rest, &path[i..], cross_dirs))
+    }
+  }
+}
+
+/// Finds the files matching an entry path, which may contain `*` as in a subpath pattern.
+fn matching_files(root: &Path, path: &str) -> Vec<String> {
+  if !path.contains('*') {
+    return if root.join(path).is_file() {
+      vec![path.to_string()]
+    } else {
+      Vec::new()
+    };
+  }
+
+  let dir = path[..path.find('*').unwrap()]
+    .rsplit_once('/')
+    .map_or("", |(dir, _)| dir);
+  WalkBuilder::new(root.join(dir))
+    .standard_filters(false)
+    .build()
+    .filter_map(|entry| {
+      let entry = entry.ok()?;
+      let rel_path = entry.path().strip_prefix(root).ok()?;
+      let rel_path = rel_path.to_str()?.replace('\\', "/");
+      (entry.file_type()?.is_file() && wildcard_match(path, &rel_path, true)).then_some(rel_path)
+    })
+    .collect()
+}
+
+/// True if a JS file has declarations next to it that Typescript will find.
+fn has_declarations(root: &Path, js_path: &str) -> bool {
+  let (stem, ext) = js_path.rsplit_once('.').unwrap_or((js_path, ""));
+  let candidates: &[&str] = match ext {
+    "cjs" => &["d.cts", "d.ts"],
+    "mjs" => &["d.mts", "d.ts"],
+    _ => &["d.ts"],
+  };
+  candidates
+    .iter()
+    .any(|ext| root.join(format!("{stem}.{ext}")).is_file())
+}
+
+/// True if the `files` list includes `path` in the published package.
+fn files_cover(files: &[String], path: &str) -> bool {
+  files.iter().any(|pattern| {
+    let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+    path == pattern
+      || path.starts_with(&format!("{pattern}/"))
+      || wildcard_match(pattern, path, false)
+  })
+}
+
+/// True if npm publishes the file at `path` even when `files` doesn't include it.
+fn always_published(path: &str) -> bool {
+  if path == "package.json" {
+    return true;
+  }
+  if path.contains('/') {
+    return false;
+  }
+  let stem = path.split('.').next().unwrap().to_ascii_uppercase();
+  matches!(stem.as_str(), "README" | "LICENSE" | "LICENCE")
+}
+
+/// Returns a description of each problem with the package's entry points.
+pub fn check_package(pkg: &Package) -> Result<Vec<String>> {
+  // Read the manifest directly, since `package_json_schema` drops nested `exports` maps.
+  let manifest_path = pkg.root.join("package.json");
+  let manifest: Value = serde_json::from_str(&fs::read_to_string(&manifest_path)?)?;
+  let included = manifest["files"].as_array().map(|files| {
+    files
+      .iter()
+      .filter_map(|file| file.as_str().map(String::from))
+      .collect::<Vec<_>>()
+  });
+
+  let mut problems = Vec::new();
+  for Entry { field, path, kind } in manifest_entries(&manifest) {
+    let files = matching_files(&pkg.root, &path);
+    if files.is_empty() {
+      problems.push(format!(
+        "`{field}` points to `{path}`, which does not exist"
+      ));
+      continue;
+    }
+
+    if kind == EntryKind::Js {
+      let is_js = |file: &&String| {
+        [".js", ".cjs", ".mjs"]
+          .iter()
+          .any(|ext| file.ends_with(ext))
+      };
+      for file in files.iter().filter(is_js) {
+        if !has_declarations(&pkg.root, file) {
+          problems.push(format!("`{field}` has no type declarations for `{file}`"));
+        }
+      }
+    }
+
+    // npm also publishes the `main` and `bin` files.
+    let published = field == "main" || kind == EntryKind::Bin || always_published(&path);
+    if let Some(included) = &included
+      && !published
+      && !files_cover(included, &path)
+    {
+      problems.push(format!(
+        "`{field}` points to `{path}`, which is not included in `files`"
+      ));
+    }
+  }
+  Ok(problems)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_wildcard_match() {
+    assert!(wildcard_match("dist/*.js", "dist/lib.js", false));
+    assert!(!wildcard_match("dist/*.js", "dist/nested/lib.js", false));
+    assert!(wildcard_match("dist/*.js", "dist/nested/lib.js", true));
+    assert!(wildcard_match("dist/**/*.js", "dist/nested/lib.js", false));
+    assert!(!wildcard_match("dist/*.js", "src/lib.js", true));
+  }
+
+  #[test]
+  fn test_files_cover() {
+    let files = vec!["dist".to_string(), "bin/*.cjs".to_string()];
+    assert!(files_cover(&files, "dist/lib.js"));
+    assert!(files_cover(&files, "bin/cli.cjs"));
+    assert!(!files_cover(&files, "lib.js"));
+    assert!(!files_cover(&files, "distro/lib.js"));
+  }
+
+  #[test]
+  fn test_always_published() {
+    assert!(always_published("package.json"));
+    assert!(always_published("README.md"));
+    assert!(always_published("license"));
+    assert!(!always_published("dist/package.json"));
+    assert!(!always_published("dist.js"));
+  }
+
+  #[test]
+  fn test_manifest_entries() {
+    let manifest = serde_json::json!({
+      "main": "dist/lib.js",
+      "exports": {
+        ".": {"types": "./dist/lib.d.ts", "default": "./dist/lib.js"},
+        "./*": "./dist/*.js"
+      },
+      "bin": {"cli": "dist/cli.cjs"}
+    });
+    let entries = manifest_entries(&manifest)
+      .into_iter()
+      .map(|entry| (entry.field, entry.path))
+      .collect::<Vec<_>>();
+    assert!(entries.contains(&("main".into(), "dist/lib.js".into())));
+    assert!(entries.contains(&("exports[\".\"][\"types\"]".into(), "dist/lib.d.ts".into())));
+    assert!(entries.contains(&("exports[\"./*\"]".into(), "dist/*.js".into())));
+    assert!(entries.contains(&("bin.cli".into(), "dist/cli.cjs".into())));
+  }
+}
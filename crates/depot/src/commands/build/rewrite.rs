@@ -0,0 +1,418 @@
+//! Post-processing of `tsc` output for libraries, so that it resolves without a bundler.
+
+use std::{
+  borrow::Cow,
+  cmp::Reverse,
+  fs,
+  path::{Component, Path, PathBuf},
+  sync::LazyLock,
+  time::Duration,
+};
+
+use anyhow::{Context, Result, anyhow};
+use ignore::WalkBuilder;
+use log::debug;
+use notify::RecursiveMode;
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::{utils, workspace::package::Package};
+
+/// Matches the specifier of `import ... from "x"`, `export ... from "x"`, `import "x"`,
+/// `import("x")`, and `require("x")`.
+static SPECIFIER: LazyLock<Regex> = LazyLock::new(|| {
+  Regex::new(r#"(\bfrom\s*|\bimport\s*\(?\s*|\brequire\s*\(\s*)(["'])([^"'\n]+)["']"#).unwrap()
+});
+
+/// Extensions tried in order when resolving an extensionless module specifier.
+const MODULE_EXTENSIONS: &[&str] = &["ts", "tsx", "mts", "cts", "js", "jsx", "mjs", "cjs"];
+
+/// A `paths` entry from a tsconfig, with targets resolved to absolute path patterns.
+struct Alias {
+  pattern: String,
+  targets: Vec<String>,
+}
+
+impl Alias {
+  /// Returns the target paths for `specifier` if it matches this alias.
+  fn resolve(&self, specifier: &str) -> Option<Vec<PathBuf>> {
+    let captured = match self.pattern.split_once('*') {
+      Some((prefix, suffix)) => specifier
+        .strip_prefix(prefix)?
+        .strip_suffix(suffix)
+        .filter(|_| specifier.len() >= prefix.len() + suffix.len())?,
+      None if specifier == self.pattern => "",
+      None => return None,
+    };
+    Some(
+      self
+        .targets
+        .iter()
+        .map(|target| PathBuf::from(target.replacen('*', captured, 1)))
+        .collect(),
+    )
+  }
+}
+
+/// Rewrites path aliases and asset imports in a library's emitted JS and declarations.
+pub struct ImportRewriter {
+  pkg: Package,
+  root_dir: PathBuf,
+  out_dir: PathBuf,
+  aliases: Vec<Alias>,
+
+  /// Whether Sass sources are compiled to CSS, rather than copied as-is.
+  compile_sass: bool,
+}
+
+impl ImportRewriter {
+  pub fn new(pkg: &Package, compile_sass: bool) -> Result<Self> {
+    let mut base_url = None;
+    let mut paths = None;
+    let mut root_dir = None;
+    let mut out_dir = None;
+
+    // Options are resolved relative to the tsconfig that sets them, and a tsconfig
+    // overrides the options of the one it extends.
+    let mut config_path = Some(pkg.root.join("tsconfig.json"));
+    while let Some(path) = config_path.take() {
+      let Ok(contents) = fs::read_to_string(&path) else {
+        break;
+      };
+      // Unresolved aliases would break the published library, so this can't be skipped.
+      let config: Value = serde_json::from_str(&utils::jsonc_to_json(&contents))
+        .with_context(|| format!("Could not parse `{}`", path.display()))?;
+      let dir = path.parent().unwrap();
+      let options = &config["compilerOptions"];
+      let resolve = |key: &str| options[key].as_str().map(|rel| normalize(&dir.join(rel)));
+      base_url = base_url.or_else(|| resolve("baseUrl"));
+      root_dir = root_dir.or_else(|| resolve("rootDir"));
+      out_dir = out_dir.or_else(|| resolve("outDir"));
+      if paths.is_none() {
+        paths = options["paths"]
+          .as_object()
+          .map(|paths| (paths.clone(), dir.to_path_buf()));
+      }
+      config_path = config["extends"]
+        .as_str()
+        .filter(|extends| extends.starts_with('.'))
+        .map(|extends| dir.join(extends));
+    }
+
+    let mut aliases = match paths {
+      Some((paths, paths_dir)) => {
+        let base = base_url.unwrap_or(paths_dir);
+        paths
+          .into_iter()
+          .map(|(pattern, targets)| {
+            let targets = targets
+              .as_array()
+              .into_iter()
+              .flatten()
+              .filter_map(Value::as_str)
+              .map(|target| normalize(&base.join(target)).display().to_string())
+              .collect();
+            Alias { pattern, targets }
+          })
+          .collect()
+      }
+      None => Vec::new(),
+    };
+    // Like TypeScript, prefer exact patterns, then the longest prefix before the `*`.
+    aliases.sort_by_key(|alias| match alias.pattern.split_once('*') {
+      Some((prefix, _)) => (true, Reverse(prefix.len())),
+      None => (false, Reverse(alias.pattern.len())),
+    });
+
+    Ok(ImportRewriter {
+      pkg: pkg.clone(),
+      root_dir: root_dir.unwrap_or_else(|| pkg.root.join("src")),
+      out_dir: out_dir.unwrap_or_else(|| pkg.root.join("dist")),
+      aliases,
+      compile_sass,
+    })
+  }
+
+  /// True if `path` is emitted by tsc and may contain import specifiers.
+  fn is_output(&self, path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+      return false;
+    };
+    path.starts_with(&self.out_dir)
+      && [".js", ".mjs", ".cjs", ".d.ts", ".d.mts", ".d.cts"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
+  }
+
+  /// Finds the source file that a specifier in `out_file` refers to, if it needs rewriting.
+  fn resolve_source(&self, specifier: &str, out_file: &Path) -> Option<PathBuf> {
+    // An alias whose targets don't exist falls through to the next matching alias.
+    let aliased = self
+      .aliases
+      .iter()
+      .filter_map(|alias| alias.resolve(specifier))
+      .flatten()
+      .find_map(|target| resolve_module(&target));
+    if aliased.is_some() {
+      return aliased;
+    }
+
+    if specifier.starts_with("./") || specifier.starts_with("../") {
+      let rel_dir = out_file.parent()?.strip_prefix(&self.out_dir).ok()?;
+      let source = normalize(&self.root_dir.join(rel_dir).join(specifier));
+      return (self.pkg.is_asset(&source) && source.is_file()).then_some(source);
+    }
+
+    None
+  }
+
+  /// Computes the specifier that `out_file` should use in place of `specifier`.
+  fn rewrite_specifier(&self, specifier: &str, out_file: &Path) -> Option<String> {
+    let source = self.resolve_source(specifier, out_file)?;
+    let rel_path = source.strip_prefix(&self.root_dir).ok()?;
+    let mut target = self.out_dir.join(rel_path);
+    let output_ext = match rel_path.extension()?.to_str()? {
+      "ts" | "tsx" | "jsx" => "js",
+      "mts" => "mjs",
+      "cts" => "cjs",
+      "scss" | "sass" if self.compile_sass => "css",
+      ext => ext,
+    };
+    target.set_extension(output_ext);
+
+    let rel_target = relative_path(out_file.parent()?, &target);
+    let rel_target = rel_target.to_str()?.replace('\\', "/");
+    Some(if rel_target.starts_with("../") {
+      rel_target
+    } else {
+      format!("./{rel_target}")
+    })
+  }
+
+  /// Rewrites every specifier in the contents of `out_file`.
+  pub fn rewrite_source<'a>(&self, out_file: &Path, contents: &'a str) -> Cow<'a, str> {
+    SPECIFIER.replace_all(contents, |caps: &Captures| {
+      let specifier = &caps[3];
+      match self.rewrite_specifier(specifier, out_file) {
+        Some(rewritten) => format!("{}{}{rewritten}{}", &caps[1], &caps[2], &caps[2]),
+        None => caps[0].to_string(),
+      }
+    })
+  }
+
+  /// Rewrites `out_file` in place if any of its specifiers changed.
+  fn rewrite_file(&self, out_file: &Path) -> Result<()> {
+    let contents = fs::read_to_string(out_file)?;
+    let rewritten = self.rewrite_source(out_file, &contents);
+    if rewritten != contents {
+      debug!("rewriting imports: {}", out_file.display());
+      fs::write(out_file, rewritten.as_bytes())?;
+    }
+    Ok(())
+  }
+
+  /// Rewrites all emitted files in the output directory.
+  pub fn rewrite_all(&self) -> Result<()> {
+    let outputs = WalkBuilder::new(&self.out_dir)
+      .standard_filters(false)
+      .build()
+      .filter_map(|entry| Some(entry.ok()?.into_path()))
+      .filter(|path| path.is_file() && self.is_output(path));
+    for path in outputs {
+      self.rewrite_file(&path)?;
+    }
+    Ok(())
+  }
+
+  /// Rewrites emitted files whenever tsc writes them. Rewriting is idempotent, so the
+  /// events caused by our own writes are harmless.
+  pub async fn watch(&self) -> Result<()> {
+    utils::create_dir_if_missing(&self.out_dir)?;
+    self.rewrite_all()?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let timeout = Duration::from_millis(500);
+    let mut debouncer = notify_debouncer_mini::new_debouncer(timeout, move |events| {
+      let _ = tx.send(events);
+    })?;
+    debouncer
+      .watcher()
+      .watch(&self.out_dir, RecursiveMode::Recursive)?;
+
+    while let Some(events) = rx.recv().await {
+      let events = events.map_err(|e| anyhow!("File watch errors: {e:?}"))?;
+      for event in events {
+        if event.path.is_file() && self.is_output(&event.path) {
+          self.rewrite_file(&event.path)?;
+        }
+      }
+    }
+
+    Ok(())
+  }
+}
+
+/// Finds the file for a module path, trying TS and JS extensions and index files.
+fn resolve_module(path: &Path) -> Option<PathBuf> {
+  if path.is_file() {
+    return Some(path.to_path_buf());
+  }
+  let with_ext = |base: &Path| {
+    MODULE_EXTENSIONS.iter().find_map(|ext| {
+      let mut file = base.as_os_str().to_owned();
+      file.push(format!(".{ext}"));
+      let file = PathBuf::from(file);
+      file.is_file().then_some(file)
+    })
+  };
+  with_ext(path).or_else(|| with_ext(&path.join("index")))
+}
+
+/// Lexically removes `.` and `..` components from a path.
+fn normalize(path: &Path) -> PathBuf {
+  let mut normalized = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        normalized.pop();
+      }
+      component => normalized.push(component),
+    }
+  }
+  normalized
+}
+
+/// The path to `to` relative to the directory `from`.
+fn relative_path(from: &Path, to: &Path) -> PathBuf {
+  let from = from.components().collect::<Vec<_>>();
+  let to = to.components().collect::<Vec<_>>();
+  let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+  let mut rel_path = PathBuf::new();
+  for _ in common..from.len() {
+    rel_path.push("..");
+  }
+  rel_path.extend(&to[common..]);
+  rel_path
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::workspace::package::{PackageManifest, Target};
+
+  #[test]
+  fn test_relative_path() {
+    let rel = |from: &str, to: &str| relative_path(Path::new(from), Path::new(to));
+    assert_eq!(rel("/a/dist", "/a/dist/utils.js"), Path::new("utils.js"));
+    assert_eq!(rel("/a/dist/x/y", "/a/dist/z.js"), Path::new("../../z.js"));
+    assert_eq!(normalize(Path::new("/a/./b/../c")), Path::new("/a/c"));
+  }
+
+  #[test]
+  fn test_alias() {
+    let alias = Alias {
+      pattern: "@/*".into(),
+      targets: vec!["/pkg/src/*".into()],
+    };
+    assert_eq!(
+      alias.resolve("@/utils/math"),
+      Some(vec![PathBuf::from("/pkg/src/utils/math")])
+    );
+    assert_eq!(alias.resolve("react"), None);
+
+    let exact = Alias {
+      pattern: "config".into(),
+      targets: vec!["/pkg/src/config.ts".into()],
+    };
+    assert!(exact.resolve("config").is_some());
+    assert!(exact.resolve("config/x").is_none());
+  }
+
+  #[test]
+  fn test_rewrite_source() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().to_path_buf();
+    utils::create_dir_if_missing(root.join("src/utils"))?;
+    fs::write(root.join("src/utils/math.ts"), "")?;
+    fs::write(root.join("src/styles.scss"), "")?;
+    fs::write(root.join("src/logo.svg"), "")?;
+    let manifest = PackageManifest::from_json(
+      serde_json::from_value(serde_json::json!({"name": "a", "depot": {"platform": "browser"}}))?,
+      &root.join("package.json"),
+    )?;
+
+    let rewriter = ImportRewriter {
+      pkg: Package::from_parts(root.clone(), manifest, 0, Target::Lib)?,
+      root_dir: root.join("src"),
+      out_dir: root.join("dist"),
+      aliases: vec![Alias {
+        pattern: "@/*".into(),
+        targets: vec![root.join("src/*").display().to_string()],
+      }],
+      compile_sass: true,
+    };
+
+    let out_file = root.join("dist/nested/lib.js");
+    let contents = r#"import { add } from "@/utils/math";
+import "../styles.scss";
+import logo from '../logo.svg';
+const m = await import("@/utils/math");
+import React from "react";
+import { local } from "./local";
+"#;
+    let expected = r#"import { add } from "../utils/math.js";
+import "../styles.css";
+import logo from '../logo.svg';
+const m = await import("../utils/math.js");
+import React from "react";
+import { local } from "./local";
+"#;
+    let rewritten = rewriter.rewrite_source(&out_file, contents);
+    assert_eq!(rewritten, expected);
+    assert_eq!(rewriter.rewrite_source(&out_file, &rewritten), expected);
+    Ok(())
+  }
+
+  #[test]
+  fn test_tsconfig_aliases() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().to_path_buf();
+    utils::create_dir_if_missing(root.join("src/utils"))?;
+    utils::create_dir_if_missing(root.join("src/ui"))?;
+    fs::write(root.join("src/utils/math.ts"), "")?;
+    fs::write(root.join("src/ui/button.tsx"), "")?;
+    fs::write(
+      root.join("tsconfig.json"),
+      r#"{
+  // Aliases are matched by the longest prefix, not in this order.
+  "compilerOptions": {
+    "paths": {
+      "@/*": ["./src/*"],
+      "@/components/*": ["./src/ui/*"],
+      "@/utils/*": ["./lib/*"], /* Missing, so `@/*` is used. */
+    },
+  },
+}"#,
+    )?;
+    let manifest = PackageManifest::from_json(
+      serde_json::from_value(serde_json::json!({"name": "a", "depot": {"platform": "browser"}}))?,
+      &root.join("package.json"),
+    )?;
+    let pkg = Package::from_parts(root.clone(), manifest, 0, Target::Lib)?;
+    let rewriter = ImportRewriter::new(&pkg, false)?;
+
+    let out_file = root.join("dist/lib.js");
+    let contents = r#"import { Button } from "@/components/button";
+import { add } from "@/utils/math";
+"#;
+    let expected = r#"import { Button } from "./ui/button.js";
+import { add } from "./utils/math.js";
+"#;
+    assert_eq!(rewriter.rewrite_source(&out_file, contents), expected);
+
+    fs::write(root.join("tsconfig.json"), "{")?;
+    assert!(ImportRewriter::new(&pkg, false).is_err());
+    Ok(())
+  }
+}
@@ -0,0 +1,219 @@
+//! Support for custom `build.mjs` scripts: the context Depot passes in, and the
+//! diagnostics that scripts report back.
+//!
+//! The context is a JSON file whose path is in the `DEPOT_CONTEXT` environment variable.
+//! Diagnostics are lines on stdout of the form `::error file=src/a.ts,line=1,col=2::message`,
+//! where the severity is `error` or `warning`, and the parameters are optional.
+
+use std::{
+  fmt,
+  io::Write,
+  path::PathBuf,
+  sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use indexmap::IndexMap;
+use tempfile::NamedTempFile;
+
+use crate::workspace::{
+  package::{Package, Platform, Target},
+  process::LineFilter,
+  profile::Profile,
+};
+
+pub const CONTEXT_VAR: &str = "DEPOT_CONTEXT";
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PackageContext {
+  name: String,
+  root: PathBuf,
+  dist: PathBuf,
+  target: Target,
+  platform: Platform,
+}
+
+impl PackageContext {
+  fn new(pkg: &Package) -> Self {
+    PackageContext {
+      name: pkg.name.to_string(),
+      root: pkg.root.clone(),
+      dist: pkg.root.join("dist"),
+      target: pkg.target,
+      platform: pkg.platform,
+    }
+  }
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BuildContext<'a> {
+  package: PackageContext,
+  profile: &'a Profile,
+  workspace_root: PathBuf,
+  /// All packages in the workspace that this package depends on, keyed by name.
+  dependencies: IndexMap<String, PackageContext>,
+  watch: bool,
+}
+
+/// Writes the context for a build script of `pkg` to a temporary file, which is deleted
+/// when the returned handle is dropped.
+pub fn write_context(pkg: &Package, watch: bool) -> Result<NamedTempFile> {
+  let ws = pkg.workspace();
+  let context = BuildContext {
+    package: PackageContext::new(pkg),
+    profile: &ws.profile,
+    workspace_root: ws.root.clone(),
+    dependencies: ws
+      .pkg_graph
+      .all_deps_for(pkg)
+      .map(|dep| (dep.name.to_string(), PackageContext::new(dep)))
+      .collect(),
+    watch,
+  };
+
+  let mut file = tempfile::Builder::new()
+    .prefix("depot-context-")
+    .suffix(".json")
+    .tempfile()?;
+  serde_json::to_writer_pretty(&mut file, &context)?;
+  file.flush()?;
+  Ok(file)
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+/// A problem reported by a build script.
+#[derive(PartialEq, Debug)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub file: Option<String>,
+  pub line: Option<u32>,
+  pub col: Option<u32>,
+  pub message: String,
+}
+
+impl Diagnostic {
+  /// Parses a line of script output, returning `None` if it is not a diagnostic.
+  pub fn parse(line: &str) -> Option<Self> {
+    let rest = line.trim_end().strip_prefix("::")?;
+    let (header, message) = rest.split_once("::")?;
+    let (severity, params) = header.split_once(' ').unwrap_or((header, ""));
+    let severity = match severity {
+      "error" => Severity::Error,
+      "warning" => Severity::Warning,
+      _ => return None,
+    };
+
+    let mut diagnostic = Diagnostic {
+      severity,
+      file: None,
+      line: None,
+      col: None,
+      message: message.to_string(),
+    };
+    for param in params.split(',').filter(|param| !param.is_empty()) {
+      let (key, value) = param.split_once('=')?;
+      match key.trim() {
+        "file" => diagnostic.file = Some(value.to_string()),
+        "line" => diagnostic.line = value.parse().ok(),
+        "col" => diagnostic.col = value.parse().ok(),
+        _ => {}
+      }
+    }
+    Some(diagnostic)
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self.severity {
+      Severity::Error => write!(f, "\u{1b}[1;31merror\u{1b}[0m: ")?,
+      Severity::Warning => write!(f, "\u{1b}[1;33mwarning\u{1b}[0m: ")?,
+    }
+    if let Some(file) = &self.file {
+      write!(f, "{file}")?;
+      if let Some(line) = self.line {
+        write!(f, ":{line}")?;
+        if let Some(col) = self.col {
+          write!(f, ":{col}")?;
+        }
+      }
+      write!(f, ": ")?;
+    }
+    write!(f, "{}", self.message)
+  }
+}
+
+/// The diagnostics reported by a build script, collected as its output is read so that
+/// they outlive the capped and clearable logs.
+#[derive(Clone, Default)]
+pub struct Diagnostics(Arc<Mutex<Vec<Diagnostic>>>);
+
+impl Diagnostics {
+  /// A filter for the script's stdout that records diagnostics and shows them formatted.
+  pub fn filter(&self) -> LineFilter {
+    let diagnostics = self.clone();
+    Arc::new(move |line| match Diagnostic::parse(&line) {
+      Some(diagnostic) => {
+        let shown = diagnostic.to_string();
+        diagnostics.0.lock().unwrap().push(diagnostic);
+        shown
+      }
+      None => line,
+    })
+  }
+
+  pub fn errors(&self) -> usize {
+    self
+      .0
+      .lock()
+      .unwrap()
+      .iter()
+      .filter(|diagnostic| diagnostic.severity == Severity::Error)
+      .count()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_parse_diagnostic() {
+    assert_eq!(
+      Diagnostic::parse("::error file=src/lib.ts,line=3,col=5::Missing export"),
+      Some(Diagnostic {
+        severity: Severity::Error,
+        file: Some("src/lib.ts".into()),
+        line: Some(3),
+        col: Some(5),
+        message: "Missing export".into(),
+      })
+    );
+
+    let warning = Diagnostic::parse("::warning::Large bundle: 2 MB").unwrap();
+    assert_eq!(warning.severity, Severity::Warning);
+    assert_eq!(warning.file, None);
+    assert_eq!(warning.message, "Large bundle: 2 MB");
+
+    assert_eq!(Diagnostic::parse("building..."), None);
+    assert_eq!(Diagnostic::parse("::notice::hello"), None);
+    assert_eq!(Diagnostic::parse("::error"), None);
+  }
+
+  #[test]
+  fn test_diagnostics_filter() {
+    let diagnostics = Diagnostics::default();
+    let filter = diagnostics.filter();
+    assert_eq!(filter("building...".into()), "building...");
+    assert!(filter("::error file=a.ts::Oops".into()).ends_with("a.ts: Oops"));
+    filter("::warning::Slow".into());
+    assert_eq!(diagnostics.errors(), 1);
+  }
+}
@@ -0,0 +1,385 @@
+use std::{
+  path::{Path, PathBuf},
+  sync::Arc,
+};
+
+use anyhow::{Context, Result, bail, ensure};
+use clap::Parser;
+use ignore::{WalkBuilder, overrides::OverrideBuilder};
+use indexmap::IndexMap;
+
+use super::{
+  build::{BuildArgs, BuildCommand},
+  doc::{DocArgs, DocCommand},
+  fix::{FixArgs, FixCommand},
+  fmt::{FmtArgs, FmtCommand},
+  init::{InitArgs, InitCommand},
+  test::{TestArgs, TestCommand},
+};
+use crate::workspace::{
+  Command, CommandDep, CommandRuntime, CoreCommand, PackageCommand, Workspace, WorkspaceCommand,
+  WorkspaceDepotConfig, package::Package,
+};
+
+/// Whether a custom command runs once per package or once for the workspace.
+#[derive(Copy, Clone, Default, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CommandScope {
+  #[default]
+  Package,
+  Workspace,
+}
+
+/// A command declared under `depot.commands` in the workspace manifest.
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CustomCommandConfig {
+  /// The tool to run from the workspace's dependencies.
+  pub exec: String,
+
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub args: Vec<String>,
+
+  #[serde(default)]
+  pub scope: CommandScope,
+
+  /// Globs for the files whose changes cause the command to rerun with `--incremental`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub inputs: Option<Vec<String>>,
+
+  /// Commands that must finish before this one, where `^` marks a command that only needs
+  /// to finish in upstream packages.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub deps: Vec<String>,
+
+  /// Built-in commands that must wait for this one.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub before: Vec<String>,
+}
+
+type CommandConfigs = Arc<IndexMap<String, CustomCommandConfig>>;
+
+#[derive(Debug)]
+pub struct CustomCommand {
+  name: String,
+  configs: CommandConfigs,
+  extra_args: Vec<String>,
+}
+
+/// The built-in command named `name`, if custom commands can run after or before it.
+fn builtin_command(name: &str) -> Option<Command> {
+  Some(match name {
+    "init" => InitCommand::new(InitArgs::default()).kind(),
+    "build" => BuildCommand::new(BuildArgs::default()).kind(),
+    "test" => TestCommand::new(TestArgs::default()).kind(),
+    "fmt" => FmtCommand::new(FmtArgs::parse_from(["fmt"])).kind(),
+    "fix" => FixCommand::new(FixArgs::parse_from(["fix"])).kind(),
+    "doc" => DocCommand::new(DocArgs::parse_from(["doc"])).kind(),
+    _ => return None,
+  })
+}
+
+/// Splits a dependency like `^build` into the command name and whether it only applies
+/// to upstream packages.
+fn parse_dep(spec: &str) -> (&str, bool) {
+  match spec.strip_prefix('^') {
+    Some(name) => (name, true),
+    None => (spec, false),
+  }
+}
+
+/// Checks that every command named in the `commands` and `deps` config exists, and that
+/// no command transitively depends on itself.
+pub fn validate(config: &WorkspaceDepotConfig) -> Result<()> {
+  let empty = IndexMap::new();
+  let commands = config.commands.as_ref().unwrap_or(&empty);
+  let empty = IndexMap::new();
+  let deps = config.deps.as_ref().unwrap_or(&empty);
+  let builtin_names = super::Command::builtin_names();
+  let is_known = |name: &str| commands.contains_key(name) || builtin_command(name).is_some();
+
+  let deps_of = |name: &str| -> Vec<String> {
+    let specs = deps
+      .get(name)
+      .or_else(|| commands.get(name).map(|config| &config.deps));
+    let mut names = match (specs, builtin_command(name)) {
+      (Some(specs), _) => specs
+        .iter()
+        .map(|spec| parse_dep(spec).0.to_string())
+        .collect(),
+      (None, Some(cmd)) => cmd.deps().iter().map(|dep| dep.name()).collect(),
+      (None, None) => Vec::new(),
+    };
+    names.extend(
+      commands
+        .iter()
+        .filter(|(_, config)| config.before.iter().any(|before| before == name))
+        .map(|(other, _)| other.clone()),
+    );
+    names
+  };
+
+  fn visit(
+    name: String,
+    deps_of: &impl Fn(&str) -> Vec<String>,
+    path: &mut Vec<String>,
+  ) -> Result<()> {
+    if path.contains(&name) {
+      bail!(
+        "Command `{name}` depends on itself: {} -> {name}",
+        path.join(" -> ")
+      );
+    }
+    let deps = deps_of(&name);
+    path.push(name);
+    for dep in deps {
+      visit(dep, deps_of, path)?;
+    }
+    path.pop();
+    Ok(())
+  }
+
+  for (name, config) in commands {
+    ensure!(
+      !builtin_names.contains(name),
+      "Custom command `{name}` has the same name as a built-in command"
+    );
+    for before in &config.before {
+      ensure!(
+        builtin_command(before).is_some(),
+        "Custom command `{name}` must run before a built-in command, not `{before}`"
+      );
+    }
+  }
+
+  let all_deps = commands
+    .iter()
+    .map(|(name, config)| (name, &config.deps))
+    .chain(deps);
+  for (name, specs) in all_deps {
+    ensure!(
+      is_known(name),
+      "Cannot set dependencies of unknown command `{name}`"
+    );
+    for spec in specs {
+      let dep = parse_dep(spec).0;
+      ensure!(
+        is_known(dep),
+        "Command `{name}` depends on unknown command `{dep}`"
+      );
+    }
+  }
+
+  for name in builtin_names
+    .iter()
+    .filter(|name| builtin_command(name).is_some())
+    .chain(commands.keys())
+  {
+    visit(name.clone(), &deps_of, &mut Vec::new())?;
+  }
+  Ok(())
+}
+
+/// The dependencies of `cmd` in this workspace. These are the ones set in `depot.deps` or
+/// in the custom command's config if any, or else the command's defaults. Custom commands
+/// that run before `cmd` are added to either.
+pub fn command_deps(ws: &Workspace, cmd: &Command) -> Vec<CommandDep> {
+  let configs = CustomCommand::configs(ws);
+  let name = cmd.name();
+  let specs = ws
+    .manifest
+    .config
+    .deps
+    .as_ref()
+    .and_then(|deps| deps.get(&name))
+    .or_else(|| configs.get(&name).map(|config| &config.deps));
+
+  let mut deps: Vec<CommandDep> = match specs {
+    Some(specs) => specs
+      .iter()
+      .map(|spec| {
+        let (dep, upstream) = parse_dep(spec);
+        let command = builtin_command(dep).unwrap_or_else(|| {
+          CustomCommand::new(dep.to_string(), Arc::clone(&configs), Vec::new()).kind()
+        });
+        CommandDep { command, upstream }
+      })
+      .collect(),
+    None => cmd.deps().into_iter().map(CommandDep::new).collect(),
+  };
+
+  deps.extend(
+    configs
+      .iter()
+      .filter(|(_, config)| config.before.contains(&name))
+      .map(|(other, _)| {
+        CommandDep::new(CustomCommand::new(other.clone(), Arc::clone(&configs), Vec::new()).kind())
+      }),
+  );
+  deps
+}
+
+/// Finds files under `root` that match any of `globs`.
+fn matching_files(root: &Path, globs: &[String]) -> Result<Vec<PathBuf>> {
+  let mut overrides = OverrideBuilder::new(root);
+  for glob in globs {
+    overrides.add(glob)?;
+  }
+  let files = WalkBuilder::new(root)
+    .overrides(overrides.build()?)
+    .build()
+    .filter_map(|entry| {
+      let entry = entry.ok()?;
+      entry.file_type()?.is_file().then(|| entry.into_path())
+    })
+    .collect();
+  Ok(files)
+}
+
+impl CustomCommand {
+  fn new(name: String, configs: CommandConfigs, extra_args: Vec<String>) -> Self {
+    CustomCommand {
+      name,
+      configs,
+      extra_args,
+    }
+  }
+
+  fn configs(ws: &Workspace) -> CommandConfigs {
+    Arc::new(ws.manifest.config.commands.clone().unwrap_or_default())
+  }
+
+  /// Creates the command invoked as `depot <name> [args]`.
+  pub fn from_cli(ws: &Workspace, mut args: Vec<String>) -> Result<Command> {
+    let name = args.remove(0);
+    let configs = Self::configs(ws);
+    ensure!(
+      configs.contains_key(&name),
+      "Unknown command `{name}`. Custom commands are declared under `depot.commands` in {}",
+      ws.root.join("package.json").display()
+    );
+    // Arguments for the tool may follow a `--`, as with built-in commands.
+    if args.first().is_some_and(|arg| arg == "--") {
+      args.remove(0);
+    }
+    Ok(Self::new(name, configs, args).kind())
+  }
+
+  fn config(&self) -> &CustomCommandConfig {
+    &self.configs[&self.name]
+  }
+
+  pub fn kind(self) -> Command {
+    match self.config().scope {
+      CommandScope::Package => Command::package(self),
+      CommandScope::Workspace => Command::workspace(self),
+    }
+  }
+
+  fn configure(&self, cmd: &mut tokio::process::Command) {
+    cmd.args(&self.config().args);
+    cmd.args(&self.extra_args);
+  }
+}
+
+impl CoreCommand for CustomCommand {
+  fn name(&self) -> String {
+    self.name.clone()
+  }
+}
+
+#[async_trait::async_trait]
+impl PackageCommand for CustomCommand {
+  async fn run_pkg(&self, pkg: &Package) -> Result<()> {
+    pkg
+      .exec(&self.config().exec, |cmd| self.configure(cmd))
+      .await
+  }
+
+  fn runtime(&self) -> CommandRuntime {
+    CommandRuntime::WaitForDependencies
+  }
+
+  fn input_files(&self, pkg: &Package) -> Vec<PathBuf> {
+    match &self.config().inputs {
+      Some(globs) => matching_files(&pkg.root, globs).unwrap_or_else(|e| {
+        log::warn!("Invalid inputs for command `{}`: {e}", self.name);
+        pkg.all_files().collect()
+      }),
+      None => pkg.all_files().collect(),
+    }
+  }
+}
+
+#[async_trait::async_trait]
+impl WorkspaceCommand for CustomCommand {
+  async fn run_ws(&self, ws: &Workspace) -> Result<()> {
+    ws.exec(&self.config().exec, |cmd| self.configure(cmd))
+      .await
+      .with_context(|| format!("Command `{}` failed", self.name))
+  }
+
+  fn input_files(&self, ws: &Workspace) -> Option<Vec<PathBuf>> {
+    let globs = self.config().inputs.as_ref()?;
+    matching_files(&ws.root, globs).ok()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn validate_json(commands: serde_json::Value, deps: serde_json::Value) -> Result<()> {
+    validate(&WorkspaceDepotConfig {
+      commands: serde_json::from_value(commands).unwrap(),
+      deps: serde_json::from_value(deps).unwrap(),
+      ..Default::default()
+    })
+  }
+
+  #[test]
+  fn test_validate_commands() {
+    let no_deps = serde_json::json!({});
+    let ok = serde_json::json!({
+      "codegen": {"exec": "tsx", "args": ["codegen.ts"], "deps": ["init"], "before": ["build"]},
+      "i18n": {"exec": "i18n-extract", "scope": "workspace", "deps": ["^codegen"]}
+    });
+    assert!(validate_json(ok, no_deps.clone()).is_ok());
+
+    let unknown = serde_json::json!({"a": {"exec": "a", "deps": ["missing"]}});
+    assert!(validate_json(unknown, no_deps.clone()).is_err());
+
+    for name in ["build", "doctor", "run", "b", "help"] {
+      let shadowed = serde_json::json!({name: {"exec": "a"}});
+      assert!(validate_json(shadowed, no_deps.clone()).is_err());
+    }
+
+    let cycle = serde_json::json!({
+      "a": {"exec": "a", "deps": ["b"]},
+      "b": {"exec": "b", "deps": ["a"]}
+    });
+    assert!(validate_json(cycle, no_deps.clone()).is_err());
+
+    // `gen` runs before `build`, but also waits for `build`.
+    let before_cycle = serde_json::json!({
+      "gen": {"exec": "gen", "deps": ["build"], "before": ["build"]}
+    });
+    assert!(validate_json(before_cycle, no_deps).is_err());
+  }
+
+  #[test]
+  fn test_validate_deps() {
+    let commands = serde_json::json!({"typecheck": {"exec": "tsc"}});
+    let ok = serde_json::json!({"test": ["^build"], "build": ["typecheck"]});
+    assert!(validate_json(commands.clone(), ok).is_ok());
+    assert_eq!(parse_dep("^build"), ("build", true));
+    assert_eq!(parse_dep("typecheck"), ("typecheck", false));
+
+    let unknown = serde_json::json!({"test": ["lint"]});
+    assert!(validate_json(commands.clone(), unknown).is_err());
+
+    // `test` depends on `build` by default.
+    let cycle = serde_json::json!({"build": ["test"]});
+    assert!(validate_json(commands, cycle).is_err());
+  }
+}
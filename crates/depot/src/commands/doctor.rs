@@ -0,0 +1,633 @@
+use std::{
+  env, fmt, fs,
+  io::{self, IsTerminal},
+  path::{Path, PathBuf},
+  time::SystemTime,
+};
+
+use anyhow::{Result, bail};
+use package_json_schema::PackageJson;
+use semver::Version;
+
+use super::build::{BuildArgs, BuildCommand};
+use crate::{
+  utils,
+  workspace::{
+    DEPOT_VERSION, PackageCommand, Workspace, WorkspaceDepotConfig, WorkspaceManifest,
+    engines::{self, Requirement, tool_version},
+    fingerprint::Fingerprints,
+    package::{Bundler, Package, Target},
+    package_manager::PackageManager,
+    version_range,
+  },
+};
+
+/// Check the environment and workspace for common problems
+#[derive(clap::Parser, Default, Debug)]
+pub struct DoctorArgs {}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Level {
+  Warning,
+  Error,
+}
+
+struct Problem {
+  level: Level,
+  message: String,
+  fix: String,
+}
+
+/// Wraps `text` in the ANSI style `code` if stdout is a terminal.
+fn styled(text: &str, code: &str) -> String {
+  if io::stdout().is_terminal() {
+    format!("\u{1b}[{code}m{text}\u{1b}[0m")
+  } else {
+    text.to_string()
+  }
+}
+
+impl fmt::Display for Problem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let label = match self.level {
+      Level::Error => styled("error", "1;31"),
+      Level::Warning => styled("warning", "1;33"),
+    };
+    write!(
+      f,
+      "{label}: {}\n  {}: {}",
+      self.message,
+      styled("fix", "1"),
+      self.fix
+    )
+  }
+}
+
+#[derive(Default)]
+struct Report {
+  problems: Vec<Problem>,
+}
+
+impl Report {
+  fn add(&mut self, level: Level, message: impl Into<String>, fix: impl Into<String>) {
+    self.problems.push(Problem {
+      level,
+      message: message.into(),
+      fix: fix.into(),
+    });
+  }
+
+  fn error(&mut self, message: impl Into<String>, fix: impl Into<String>) {
+    self.add(Level::Error, message, fix);
+  }
+
+  fn warning(&mut self, message: impl Into<String>, fix: impl Into<String>) {
+    self.add(Level::Warning, message, fix);
+  }
+
+  fn finish(self) -> Result<()> {
+    println!();
+    for problem in &self.problems {
+      println!("{problem}\n");
+    }
+
+    let errors = self
+      .problems
+      .iter()
+      .filter(|problem| problem.level == Level::Error)
+      .count();
+    let warnings = self.problems.len() - errors;
+    if errors > 0 {
+      bail!("Found {errors} error(s) and {warnings} warning(s)");
+    } else if warnings > 0 {
+      println!("Found {warnings} warning(s)");
+    } else {
+      println!("No problems found");
+    }
+    Ok(())
+  }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+  fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+pub struct DoctorCommand {
+  #[allow(unused)]
+  args: DoctorArgs,
+}
+
+impl DoctorCommand {
+  pub fn new(args: DoctorArgs) -> Self {
+    DoctorCommand { args }
+  }
+
+  /// Reports an error if `version` of `name` doesn't satisfy its requirement.
+  fn check_version(
+    report: &mut Report,
+    name: &str,
+    version: &Version,
+    requirement: Result<Option<Requirement>>,
+  ) {
+    match requirement {
+      Ok(Some(requirement)) if !requirement.range.matches(version) => report.error(
+        format!(
+          "{name} {version} does not satisfy {}, as required by {}",
+          requirement.range, requirement.source
+        ),
+        format!("Install {name} {}", requirement.range),
+      ),
+      Ok(_) => {}
+      Err(e) => report.warning(
+        format!("{e:#}"),
+        "Fix the range in package.json to be like \">=1.2\"",
+      ),
+    }
+  }
+
+  fn check_node(report: &mut Report, manifest: Option<&PackageJson>) {
+    let Some(node) = utils::find_node() else {
+      report.error(
+        "Could not find `node` on your path",
+        "Install NodeJS: https://nodejs.org/en/download/package-manager",
+      );
+      return;
+    };
+    let version = match tool_version(&node) {
+      Ok(version) => version,
+      Err(e) => {
+        report.error(
+          format!("Could not get the version of `node`: {e}"),
+          "Check that `node --version` works",
+        );
+        return;
+      }
+    };
+
+    println!("node {version}");
+    let requirement = engines::node_requirement(manifest).map(Some);
+    Self::check_version(report, "node", &version, requirement);
+  }
+
+  fn check_package_manager(
+    report: &mut Report,
+    root: Option<&Path>,
+    pm: PackageManager,
+    manifest: Option<&PackageJson>,
+  ) {
+    let version = match pm.find(root).and_then(|bin| tool_version(&bin)) {
+      Ok(version) => version,
+      Err(e) => {
+        report.error(
+          format!("Could not get the version of `{pm}`: {e}"),
+          format!("Install {pm}: {}", pm.install_url()),
+        );
+        return;
+      }
+    };
+
+    println!("{pm} {version}");
+    if let Err(e) = pm.ensure_supported(&version) {
+      report.error(
+        format!("{e}"),
+        format!("Install {pm} 1: {}", pm.install_url()),
+      );
+    }
+    let requirement = engines::package_manager_requirement(manifest, pm);
+    Self::check_version(report, pm.name(), &version, requirement);
+
+    let Some(manifest) = manifest else {
+      return;
+    };
+    let Some(field) = manifest.package_manager.as_deref() else {
+      return;
+    };
+    if PackageManager::from_field(field) != Some(pm) {
+      report.warning(
+        format!("`packageManager` is {field}, but the workspace uses {pm}"),
+        "Make `packageManager` agree with `depot.package-manager`",
+      );
+      return;
+    }
+    // Compatible versions are allowed, but corepack would run exactly this one.
+    if let Some(expected) = engines::pinned_version(manifest, pm)
+      && version_range::parse_version(expected).is_ok_and(|expected| expected != version)
+    {
+      report.warning(
+        format!("`packageManager` is {field}, but {pm} {version} is installed"),
+        format!("Run `corepack enable` to use the pinned version, or install {pm}@{expected}"),
+      );
+    }
+  }
+
+  fn check_depot_version(report: &mut Report, created: &str) {
+    let (Ok(binary), Ok(created)) = (
+      version_range::parse_version(DEPOT_VERSION),
+      version_range::parse_version(created),
+    ) else {
+      report.error(
+        format!("Invalid `depot.depot-version`: {created}"),
+        format!("Set `depot.depot-version` to \"{DEPOT_VERSION}\""),
+      );
+      return;
+    };
+
+    if created > binary {
+      report.error(
+        format!(
+          "Workspace was created with Depot v{created}, which is newer than this Depot v{binary}"
+        ),
+        "Upgrade Depot with `cargo install depot-js --locked`",
+      );
+    } else if (created.major, created.minor) != (binary.major, binary.minor) {
+      report.warning(
+        format!("Workspace was created with Depot v{created}, but this is Depot v{binary}"),
+        format!(
+          "Check that the workspace is compatible, then set `depot.depot-version` to \"{DEPOT_VERSION}\""
+        ),
+      );
+    }
+  }
+
+  fn load_manifest(report: &mut Report, root: &Path) -> Option<PackageJson> {
+    let path = root.join("package.json");
+    let manifest = fs::read_to_string(&path)
+      .map_err(anyhow::Error::from)
+      .and_then(|contents| Ok(PackageJson::try_from(contents)?));
+    match manifest {
+      Ok(manifest) => Some(manifest),
+      Err(e) => {
+        report.error(
+          format!("Could not read {}: {e}", path.display()),
+          "Fix the syntax of package.json",
+        );
+        None
+      }
+    }
+  }
+
+  fn check_workspace(
+    report: &mut Report,
+    root: &Path,
+    manifest: &PackageJson,
+  ) -> Option<WorkspaceManifest> {
+    let path = root.join("package.json");
+    let has_config = manifest
+      .other
+      .as_ref()
+      .is_some_and(|other| other.contains_key("depot"));
+    if !has_config {
+      report.error(
+        format!("{} has no \"depot\" key", path.display()),
+        format!("Add `\"depot\": {{\"depot-version\": \"{DEPOT_VERSION}\"}}` to package.json"),
+      );
+      return None;
+    }
+
+    match WorkspaceManifest::from_json(manifest.clone(), &path) {
+      Ok(manifest) => {
+        Self::check_depot_version(report, &manifest.config.depot_version);
+        Some(manifest)
+      }
+      Err(e) => {
+        report.error(
+          format!("Invalid \"depot\" config in {}: {e}", path.display()),
+          "Fix the config to match the options described in the Depot README",
+        );
+        None
+      }
+    }
+  }
+
+  /// Loads each package and checks that it has the config files that `depot new` generates.
+  fn check_packages(
+    report: &mut Report,
+    root: &Path,
+    config: &WorkspaceDepotConfig,
+  ) -> Vec<Package> {
+    let pkg_roots = match Workspace::package_roots(root) {
+      Ok(roots) => roots,
+      Err(e) => {
+        report.error(
+          format!("Could not read {}: {e}", root.join("packages").display()),
+          "Check the permissions of the packages directory",
+        );
+        return Vec::new();
+      }
+    };
+
+    let mut packages = Vec::new();
+    for (index, pkg_root) in pkg_roots.into_iter().enumerate() {
+      let pkg = match Package::load(&pkg_root, index) {
+        Ok(pkg) => pkg,
+        Err(e) => {
+          report.error(
+            format!("Could not load package at {}: {e}", pkg_root.display()),
+            "Give the package.json a \"depot\" key with a \"platform\", like `\"depot\": {\"platform\": \"browser\"}`",
+          );
+          continue;
+        }
+      };
+
+      let has_file = |files: &[&str], in_root: bool| {
+        files
+          .iter()
+          .any(|file| pkg.root.join(file).exists() || (in_root && root.join(file).exists()))
+      };
+      if !has_file(&["tsconfig.json"], true) {
+        report.error(
+          format!("Package `{}` has no tsconfig.json", pkg.name),
+          "Add a tsconfig.json, like the one generated by `depot new`",
+        );
+      }
+
+      let linter = pkg
+        .manifest
+        .config
+        .linter
+        .or(config.linter)
+        .unwrap_or_default();
+      if !has_file(linter.config_files(), true) {
+        report.warning(
+          format!(
+            "Package `{}` has no {} config",
+            pkg.name,
+            linter.config_files()[0]
+          ),
+          format!(
+            "Add a {}, like the one generated by `depot new`",
+            linter.config_files()[0]
+          ),
+        );
+      }
+
+      let uses_vite = match pkg.target {
+        Target::Site => pkg.uses_vike() || pkg.bundler() == Bundler::Vite,
+        Target::Script => pkg.bundler() == Bundler::Vite,
+        Target::Lib => pkg.manifest.config.bundle.is_some(),
+      };
+      if uses_vite && !has_file(&["vite.config.ts", "vite.config.mts"], false) {
+        report.error(
+          format!("Package `{}` has no vite.config.ts", pkg.name),
+          "Add a vite.config.ts, like the one generated by `depot new`",
+        );
+      }
+
+      packages.push(pkg);
+    }
+    packages
+  }
+
+  fn check_fingerprints(report: &mut Report, root: &Path, packages: &[Package]) {
+    let path = Fingerprints::file_path(root);
+    if !path.exists() {
+      return;
+    }
+    let fix = format!("Delete {}", path.display());
+    let fingerprints = match Fingerprints::load(root) {
+      Ok(fingerprints) => fingerprints,
+      Err(e) => {
+        report.error(format!("Could not read {}: {e}", path.display()), fix);
+        return;
+      }
+    };
+
+    let now = SystemTime::now();
+    if fingerprints.times().any(|(_, time)| time > now) {
+      report.warning(
+        "Some fingerprints are from the future, so --incremental may skip commands that should run",
+        fix.clone(),
+      );
+    }
+
+    let build = BuildCommand::new(BuildArgs::default());
+    for pkg in packages {
+      let key = build.pkg_key(pkg);
+      let built = fingerprints.times().any(|(other, _)| other == key);
+      if built && !pkg.root.join("dist").exists() {
+        report.warning(
+          format!(
+            "Package `{}` has a build fingerprint but no dist directory, so --incremental may skip its build",
+            pkg.name
+          ),
+          format!("Run `depot build` without --incremental, or {}", fix.to_lowercase()),
+        );
+      }
+    }
+  }
+
+  fn check_node_modules(report: &mut Report, root: &Path, pm: PackageManager) {
+    let node_modules = root.join("node_modules");
+    if !node_modules.exists() {
+      report.error("Dependencies are not installed", "Run `depot init`");
+      return;
+    }
+
+    let Some(lockfile) = pm.find_lockfile(root) else {
+      report.warning(
+        format!("The workspace has no {pm} lockfile"),
+        "Run `depot init` to create one",
+      );
+      return;
+    };
+    let lockfile_name = lockfile.file_name().unwrap().to_string_lossy();
+    match (
+      modified(&lockfile),
+      modified(&node_modules.join(pm.install_marker())),
+    ) {
+      (Some(locked), Some(installed)) if locked > installed => report.error(
+        format!("node_modules is out of date with {lockfile_name}"),
+        "Run `depot init`",
+      ),
+      (Some(_), None) => report.warning(
+        format!("node_modules was not installed by {pm}"),
+        "Delete node_modules, then run `depot init`",
+      ),
+      _ => {}
+    }
+  }
+
+  pub fn run(self) -> Result<()> {
+    let mut report = Report::default();
+    let cwd = env::current_dir()?;
+    let root: Option<PathBuf> = match Workspace::find_root(&cwd) {
+      Ok(root) => Some(root),
+      Err(e) => {
+        report.error(
+          e.to_string(),
+          "Run Depot inside a workspace, or create one with `depot new`",
+        );
+        None
+      }
+    };
+    let manifest = root
+      .as_deref()
+      .and_then(|root| Self::load_manifest(&mut report, root));
+
+    Self::check_node(&mut report, manifest.as_ref());
+
+    let (Some(root), Some(manifest)) = (root, manifest) else {
+      Self::check_package_manager(&mut report, None, PackageManager::default(), None);
+      return report.finish();
+    };
+
+    let ws_manifest = Self::check_workspace(&mut report, &root, &manifest);
+    let pm = match &ws_manifest {
+      Some(ws_manifest) => PackageManager::resolve(&root, ws_manifest).unwrap_or_else(|e| {
+        report.error(
+          format!("{e}"),
+          "Set `packageManager` to a supported version, like \"yarn@1.22.22\"",
+        );
+        PackageManager::detect(&root, manifest.package_manager.as_deref())
+      }),
+      None => PackageManager::detect(&root, manifest.package_manager.as_deref()),
+    };
+    Self::check_package_manager(&mut report, Some(&root), pm, Some(&manifest));
+
+    if let Some(ws_manifest) = &ws_manifest {
+      let packages = Self::check_packages(&mut report, &root, &ws_manifest.config);
+      Self::check_fingerprints(&mut report, &root, &packages);
+    }
+    Self::check_node_modules(&mut report, &root, pm);
+
+    report.finish()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::time::{Duration, UNIX_EPOCH};
+
+  use serde_json::json;
+
+  use super::*;
+
+  fn problems(report: &Report) -> Vec<(Level, &str)> {
+    report
+      .problems
+      .iter()
+      .map(|problem| (problem.level, problem.message.as_str()))
+      .collect()
+  }
+
+  fn write(path: &Path, contents: &str) -> Result<()> {
+    utils::create_dir_if_missing(path.parent().unwrap())?;
+    fs::write(path, contents)?;
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_depot_version() {
+    let check = |created: &str| {
+      let mut report = Report::default();
+      DoctorCommand::check_depot_version(&mut report, created);
+      report
+        .problems
+        .iter()
+        .map(|problem| problem.level)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(check(DEPOT_VERSION), []);
+    assert_eq!(check("0.0.1"), [Level::Warning]);
+    assert_eq!(check("999.0.0"), [Level::Error]);
+    assert_eq!(check("not-a-version"), [Level::Error]);
+  }
+
+  #[test]
+  fn test_check_packages() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    write(&root.join("biome.json"), "{}")?;
+    let packages = [
+      ("lib", json!({"target": "lib"}), "src/lib.ts", false),
+      ("script", json!({"target": "script"}), "src/main.ts", true),
+      (
+        "site",
+        json!({"target": "site", "bundler": "esbuild", "linter": "eslint"}),
+        "src/index.ts",
+        true,
+      ),
+    ];
+    for (name, mut config, source, has_tsconfig) in packages {
+      config["platform"] = json!("browser");
+      let pkg_root = root.join("packages").join(name);
+      let manifest = json!({"name": name, "depot": config});
+      write(&pkg_root.join("package.json"), &manifest.to_string())?;
+      write(&pkg_root.join(source), "")?;
+      if has_tsconfig {
+        write(&pkg_root.join("tsconfig.json"), "{}")?;
+      }
+    }
+    write(&root.join("packages/README.md"), "")?;
+
+    let mut report = Report::default();
+    let packages =
+      DoctorCommand::check_packages(&mut report, root, &WorkspaceDepotConfig::default());
+    assert_eq!(packages.len(), 3);
+    assert_eq!(
+      problems(&report),
+      [
+        (Level::Error, "Package `lib` has no tsconfig.json"),
+        (Level::Error, "Package `script` has no vite.config.ts"),
+        (
+          Level::Warning,
+          "Package `site` has no eslint.config.mjs config"
+        ),
+      ]
+    );
+    Ok(())
+  }
+
+  #[test]
+  fn test_check_node_modules() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    let pm = PackageManager::Pnpm;
+    let check = || {
+      let mut report = Report::default();
+      DoctorCommand::check_node_modules(&mut report, root, pm);
+      report
+        .problems
+        .into_iter()
+        .map(|problem| (problem.level, problem.message))
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+      check(),
+      [(Level::Error, "Dependencies are not installed".into())]
+    );
+
+    utils::create_dir(root.join("node_modules"))?;
+    assert_eq!(
+      check(),
+      [(Level::Warning, "The workspace has no pnpm lockfile".into())]
+    );
+
+    write(&root.join("pnpm-lock.yaml"), "")?;
+    assert_eq!(
+      check(),
+      [(
+        Level::Warning,
+        "node_modules was not installed by pnpm".into()
+      )]
+    );
+
+    let marker = root.join("node_modules").join(pm.install_marker());
+    write(&marker, "")?;
+    assert_eq!(check(), []);
+
+    fs::File::options()
+      .write(true)
+      .open(&marker)?
+      .set_modified(UNIX_EPOCH + Duration::from_secs(1))?;
+    assert_eq!(
+      check(),
+      [(
+        Level::Error,
+        "node_modules is out of date with pnpm-lock.yaml".into()
+      )]
+    );
+    Ok(())
+  }
+}
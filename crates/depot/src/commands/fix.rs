@@ -2,12 +2,12 @@ use anyhow::{Context, Result};
 
 use crate::workspace::{Command, CoreCommand, PackageCommand, package::Package};
 
-/// Fix biome issues where possible
+/// Fix lint issues where possible
 #[derive(clap::Parser, Debug)]
 pub struct FixArgs {
-  /// Additional arguments to pass to prettier
+  /// Additional arguments to pass to the linter
   #[arg(last = true)]
-  pub biome_args: Option<String>,
+  pub linter_args: Option<String>,
 }
 
 #[derive(Debug)]
@@ -35,19 +35,24 @@ impl CoreCommand for FixCommand {
 #[async_trait::async_trait]
 impl PackageCommand for FixCommand {
   async fn run_pkg(&self, pkg: &Package) -> Result<()> {
-    let extra = match &self.args.biome_args {
-      Some(args) => shlex::split(args).context("Failed to parse prettier args")?,
+    let extra = match &self.args.linter_args {
+      Some(args) => shlex::split(args).context("Failed to parse linter args")?,
       None => Vec::new(),
     };
 
-    let _ = pkg
-      .exec("biome", |cmd| {
-        cmd.arg("check");
-        cmd.arg("--fix");
-        cmd.args(pkg.source_files());
-        cmd.args(extra);
-      })
-      .await;
+    // Formatting runs after linting, since lint fixes may leave code unformatted.
+    for (i, (program, args)) in pkg.linter().fix().iter().enumerate() {
+      let _ = pkg
+        .exec(program, |cmd| {
+          cmd.args(*args);
+          cmd.args(pkg.source_files());
+          // Extra arguments are for the linter, which runs first.
+          if i == 0 {
+            cmd.args(&extra);
+          }
+        })
+        .await;
+    }
     Ok(())
   }
 }
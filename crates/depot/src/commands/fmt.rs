@@ -2,16 +2,16 @@ use anyhow::{Context, Result};
 
 use crate::workspace::{Command, CoreCommand, PackageCommand, package::Package};
 
-/// Format source files with biome
+/// Format source files with the configured formatter
 #[derive(clap::Parser, Debug)]
 pub struct FmtArgs {
   /// If true, don't write to files and instead fail if they aren't formatted
   #[arg(short, long, action)]
   pub check: bool,
 
-  /// Additional arguments to pass to biome
+  /// Additional arguments to pass to the formatter
   #[arg(last = true)]
-  pub biome_args: Option<String>,
+  pub formatter_args: Option<String>,
 }
 
 #[derive(Debug)]
@@ -39,17 +39,15 @@ impl CoreCommand for FmtCommand {
 #[async_trait::async_trait]
 impl PackageCommand for FmtCommand {
   async fn run_pkg(&self, pkg: &Package) -> Result<()> {
-    let extra = match &self.args.biome_args {
-      Some(args) => shlex::split(args).context("Failed to parse biome args")?,
+    let extra = match &self.args.formatter_args {
+      Some(args) => shlex::split(args).context("Failed to parse formatter args")?,
       None => Vec::new(),
     };
 
+    let (program, args) = pkg.linter().format(self.args.check);
     pkg
-      .exec("biome", |cmd| {
-        cmd.arg("format");
-        if !self.args.check {
-          cmd.arg("--write");
-        }
+      .exec(program, |cmd| {
+        cmd.args(args);
         cmd.args(pkg.source_files());
         cmd.args(extra);
       })
@@ -6,9 +6,9 @@ use anyhow::{Context, Result};
 /// Initialize a workspace
 #[derive(clap::Parser, Default, Debug)]
 pub struct InitArgs {
-  /// Additional arguments to pass to vitest
+  /// Additional arguments to pass to the package manager
   #[arg(last = true)]
-  pub pnpm_args: Option<String>,
+  pub install_args: Option<String>,
 }
 
 #[derive(Debug)]
@@ -36,20 +36,19 @@ impl CoreCommand for InitCommand {
 #[async_trait::async_trait]
 impl WorkspaceCommand for InitCommand {
   async fn run_ws(&self, ws: &Workspace) -> Result<()> {
-    let pnpm_args = match &self.args.pnpm_args {
-      Some(pnpm_args) => Some(shlex::split(pnpm_args).context("Failed to parse pnpm args")?),
+    let install_args = match &self.args.install_args {
+      Some(install_args) => {
+        Some(shlex::split(install_args).context("Failed to parse package manager args")?)
+      }
       None => None,
     };
 
-    ws.exec("pnpm", |cmd| {
-      cmd.arg("install");
-
-      if ws.common.offline {
-        cmd.arg("--offline");
-      }
+    let pm = ws.package_manager;
+    ws.exec(pm.name(), |cmd| {
+      cmd.args(pm.install_args(ws.common.offline));
 
-      if let Some(pnpm_args) = pnpm_args {
-        cmd.args(pnpm_args);
+      if let Some(install_args) = install_args {
+        cmd.args(install_args);
       }
     })
     .await
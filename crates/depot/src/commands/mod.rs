@@ -1,12 +1,18 @@
 pub mod build;
 pub mod clean;
+pub mod custom;
 pub mod doc;
+pub mod doctor;
 pub mod fix;
 pub mod fmt;
 pub mod init;
 pub mod new;
+pub mod run;
+pub mod script;
 pub mod test;
 
+use std::iter;
+
 #[derive(clap::Subcommand)]
 pub enum Command {
   #[clap(visible_alias = "n")]
@@ -15,6 +21,9 @@ pub enum Command {
   #[clap(visible_alias = "b")]
   Build(build::BuildArgs),
 
+  #[clap(visible_alias = "r")]
+  Run(run::RunArgs),
+
   #[clap(visible_alias = "t")]
   Test(test::TestArgs),
 
@@ -24,9 +33,32 @@ pub enum Command {
   #[clap(visible_alias = "d")]
   Doc(doc::DocArgs),
 
+  #[clap(visible_alias = "s")]
+  Script(script::ScriptArgs),
+
   Fmt(fmt::FmtArgs),
 
   Fix(fix::FixArgs),
 
   Init(init::InitArgs),
+
+  Doctor(doctor::DoctorArgs),
+
+  /// A command declared under `depot.commands` in the workspace manifest
+  #[command(external_subcommand)]
+  Custom(Vec<String>),
+}
+
+impl Command {
+  /// The names and aliases of the built-in subcommands, which are matched before
+  /// custom commands.
+  pub fn builtin_names() -> Vec<String> {
+    let mut cmd = <Self as clap::Subcommand>::augment_subcommands(clap::Command::new("depot"));
+    cmd.build();
+    cmd
+      .get_subcommands()
+      .flat_map(|sub| iter::once(sub.get_name()).chain(sub.get_all_aliases()))
+      .map(String::from)
+      .collect()
+  }
 }
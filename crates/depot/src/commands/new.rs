@@ -18,7 +18,12 @@ use crate::{
   CommonArgs, utils,
   workspace::{
     DEPOT_VERSION, Workspace, WorkspaceDepotConfig,
-    package::{PackageDepotConfig, PackageName, Platform, Target},
+    engines::ToolVersion,
+    linter::Linter,
+    package::{
+      BundleConfig, BundleFormat, Bundler, PackageDepotConfig, PackageName, Platform, Target,
+    },
+    package_manager::{DependencyKind, PackageManager},
   },
 };
 
@@ -57,6 +62,8 @@ const CSS: &str = r#"@import "normalize.css/normalize.css";
 
 const VITEST_SETUP: &str = include_str!("configs/setup.ts");
 
+const ESLINT_CONFIG: &str = include_str!("configs/eslint.config.mjs");
+
 /// Create a new Depot workspace
 #[derive(clap::Parser)]
 #[allow(clippy::struct_excessive_bools)]
@@ -83,13 +90,29 @@ pub struct NewArgs {
   #[arg(long, action)]
   pub vike: bool,
 
+  /// Bundle a library into ESM and CJS with Vite library mode
+  #[arg(long, action)]
+  pub bundle: bool,
+
   /// Don't attempt to download packages from the web
   #[arg(long, action)]
   pub offline: bool,
 
-  /// Prefer local pnpm cache if available
+  /// Prefer the local package cache if available
   #[arg(long, action)]
   pub prefer_offline: bool,
+
+  /// Package manager to install dependencies with, defaulting to the workspace's or pnpm
+  #[arg(long, value_enum)]
+  pub package_manager: Option<PackageManager>,
+
+  /// Linter and formatter to configure, defaulting to the workspace's or biome
+  #[arg(long, value_enum)]
+  pub linter: Option<Linter>,
+
+  /// Bundler for a script or site, defaulting to vite
+  #[arg(long, value_enum)]
+  pub bundler: Option<Bundler>,
 }
 
 pub struct NewCommand {
@@ -145,6 +168,21 @@ fn test_json_merge() {
   );
 }
 
+#[test]
+fn test_bundled_exports() {
+  assert_eq!(
+    NewCommand::bundled_exports(&[BundleFormat::Es, BundleFormat::Cjs]),
+    json!({".": {
+      "import": {"types": "./dist/lib.d.ts", "default": "./dist/lib.js"},
+      "require": {"types": "./dist/lib.d.cts", "default": "./dist/lib.cjs"}
+    }})
+  );
+  assert_eq!(
+    NewCommand::bundled_exports(&[BundleFormat::Cjs]),
+    json!({".": {"require": {"types": "./dist/lib.d.cts", "default": "./dist/lib.cjs"}}})
+  );
+}
+
 type FileVec = Vec<(PathBuf, Cow<'static, str>)>;
 
 impl NewCommand {
@@ -153,22 +191,74 @@ impl NewCommand {
     Self { args, ws_opt }
   }
 
+  fn package_manager(&self) -> PackageManager {
+    match &self.ws_opt {
+      Some(ws) => ws.package_manager,
+      None => self.args.package_manager.unwrap_or_default(),
+    }
+  }
+
+  /// The package manager to record in the config of a new workspace, if it would not
+  /// otherwise be detected.
+  fn package_manager_config(&self) -> Option<PackageManager> {
+    let pm = self.package_manager();
+    (self.ws_opt.is_none() && pm != PackageManager::default()).then_some(pm)
+  }
+
+  fn workspace_linter(&self) -> Option<Linter> {
+    self
+      .ws_opt
+      .as_ref()
+      .and_then(|ws| ws.manifest.config.linter)
+  }
+
+  fn linter(&self) -> Linter {
+    self
+      .args
+      .linter
+      .or_else(|| self.workspace_linter())
+      .unwrap_or_default()
+  }
+
+  /// The linter to record in the config of a new package, if it differs from the
+  /// workspace's.
+  fn package_linter_config(&self) -> Option<Linter> {
+    let linter = self.linter();
+    (self.ws_opt.is_some() && linter != self.workspace_linter().unwrap_or_default())
+      .then_some(linter)
+  }
+
+  /// The linter to record in the config of a new workspace, if it isn't the default.
+  fn workspace_linter_config(&self) -> Option<Linter> {
+    let linter = self.linter();
+    (self.ws_opt.is_none() && linter != Linter::default()).then_some(linter)
+  }
+
   fn new_workspace(self, root: &Path) -> Result<()> {
     utils::create_dir(root.join("packages"))?;
 
-    let manifest = json!({
+    let config = WorkspaceDepotConfig {
+      depot_version: DEPOT_VERSION.to_string(),
+      package_manager: self.package_manager_config(),
+      linter: self.workspace_linter_config(),
+      ..Default::default()
+    };
+    let mut manifest = json!({
       "private": true,
-      "depot": {
-        "depot-version": DEPOT_VERSION
-      }
+      "depot": config,
     });
-    let mut files: FileVec = vec![(
+    let mut files: FileVec = Vec::new();
+    if self.package_manager().uses_workspace_file() {
+      files.extend(Self::make_pnpm_workspace(true));
+    } else {
+      manifest["workspaces"] = json!(["packages/*"]);
+    }
+    files.push((
       "package.json".into(),
       serde_json::to_string_pretty(&manifest)?.into(),
-    )];
-    files.extend(Self::make_pnpm_workspace(true));
+    ));
     files.extend(self.make_tsconfig()?);
-    files.extend(self.make_biome_config()?);
+    files.extend(self.make_lint_config()?);
     files.extend(self.make_typedoc_config()?);
     files.extend(Self::make_gitignore());
 
@@ -293,6 +383,27 @@ packages:
     Ok(files)
   }
 
+  fn make_lint_config(&self) -> Result<FileVec> {
+    match self.linter() {
+      Linter::Biome => self.make_biome_config(),
+      Linter::Eslint => Self::make_eslint_config(),
+    }
+  }
+
+  fn make_eslint_config() -> Result<FileVec> {
+    let prettier = json!({
+      "arrowParens": "avoid",
+      "trailingComma": "none"
+    });
+    Ok(vec![
+      ("eslint.config.mjs".into(), ESLINT_CONFIG.into()),
+      (
+        ".prettierrc.json".into(),
+        serde_json::to_string_pretty(&prettier)?.into(),
+      ),
+    ])
+  }
+
   fn make_biome_config(&self) -> Result<FileVec> {
     let mut config = json!({
       "$schema": "https://biomejs.dev/schemas/1.8.2/schema.json",
@@ -415,6 +526,25 @@ minify: false,"#,
         );
         config.push(("build", full_obj.into()));
       }
+      Target::Lib if self.args.bundle => {
+        imports.push(("{ resolve }", "node:path"));
+        imports.push(("dts", "vite-plugin-dts"));
+        uses_manifest = true;
+        let build_config = format!(
+          r#"{{
+  lib: {{
+    entry: resolve(__dirname, "src/{}"),
+    formats,
+    fileName: format => (format === "es" ? "lib.js" : "lib.cjs")
+  }},
+  rollupOptions: {{
+    external: id => external.some(dep => id === dep || id.startsWith(`${{dep}}/`))
+  }}
+}}"#,
+          entry_point.unwrap()
+        );
+        config.push(("build", build_config.into()));
+      }
       Target::Lib => {}
     }
 
@@ -422,7 +552,8 @@ minify: false,"#,
     config.push((
       "define",
       r#"{
-  "process.env.NODE_ENV": JSON.stringify(mode)
+  "process.env.NODE_ENV": JSON.stringify(mode),
+  ...JSON.parse(process.env.DEPOT_DEFINE || "{}")
 }"#
         .into(),
     ));
@@ -434,6 +565,15 @@ minify: false,"#,
     if self.args.vike {
       plugins.push("vike({ prerender: true })");
     }
+    if self.args.bundle {
+      // CommonJS consumers need declarations with a `.d.cts` extension.
+      plugins.push(
+        r#"dts({
+  rollupTypes: manifest.depot.bundle.dts !== "files",
+  afterBuild: () => formats.includes("cjs") && fs.copyFileSync("dist/lib.d.ts", "dist/lib.d.cts")
+})"#,
+      );
+    }
     if !plugins.is_empty() {
       config.push(("plugins", format!("[{}]", plugins.join(", ")).into()));
     }
@@ -473,15 +613,23 @@ minify: false,"#,
     } else {
       ""
     };
+    let external_str = if self.args.bundle {
+      "let external = Object.keys(manifest.dependencies || {}).concat(
+  Object.keys(manifest.peerDependencies || {})
+);
+let formats = manifest.depot.bundle.formats ?? [\"es\", \"cjs\"];\n"
+    } else {
+      ""
+    };
     let mut src = format!(
       "{imports_str}
-{manifest_str}export default defineConfig(({{ mode }}) => ({{
+{manifest_str}{external_str}export default defineConfig(({{ mode }}) => ({{
 {config_str}
 }}));
 "
     );
 
-    if target.is_site() || target.is_script() {
+    if target.is_site() || target.is_script() || self.args.bundle {
       files.push(("vite.config.ts".into(), src.into()));
     } else {
       src.insert_str(0, "/// <reference types=\"vitest\" />\n");
@@ -491,6 +639,24 @@ minify: false,"#,
     files
   }
 
+  /// The `exports` of a bundled library, with declarations for each module format.
+  fn bundled_exports(formats: &[BundleFormat]) -> Value {
+    let conditions = formats
+      .iter()
+      .map(|format| match format {
+        BundleFormat::Es => (
+          "import".to_string(),
+          json!({"types": "./dist/lib.d.ts", "default": "./dist/lib.js"}),
+        ),
+        BundleFormat::Cjs => (
+          "require".to_string(),
+          json!({"types": "./dist/lib.d.cts", "default": "./dist/lib.cjs"}),
+        ),
+      })
+      .collect::<serde_json::Map<_, _>>();
+    json!({".": conditions})
+  }
+
   fn make_typedoc_config(&self) -> Result<FileVec> {
     let mut config = json!({
       "name": &self.args.name.name,
@@ -554,28 +720,29 @@ minify: false,"#,
     vec![(".gitignore".into(), gitignore.into())]
   }
 
-  fn run_pnpm(&self, f: impl Fn(&mut Command)) -> Result<()> {
-    let pnpm_bin = utils::find_pnpm(None).context("Could not find pnpm")?;
+  fn run_package_manager(&self, f: impl Fn(&mut Command)) -> Result<()> {
+    let pm = self.package_manager();
+    let pm_bin = pm.find(None)?;
     cfg_if::cfg_if! {
       if #[cfg(windows)] {
         let mut cmd = Command::new("sh");
-        cmd.arg(pnpm_bin);
+        cmd.arg(pm_bin);
       } else {
-        let mut cmd = Command::new(pnpm_bin);
+        let mut cmd = Command::new(pm_bin);
       }
     }
     f(&mut cmd);
 
     if self.args.offline {
-      cmd.arg("--offline");
+      cmd.arg(pm.offline_flag());
     }
 
     if self.args.prefer_offline {
-      cmd.arg("--prefer-offline");
+      cmd.arg(pm.prefer_offline_flag());
     }
 
     let status = cmd.status()?;
-    ensure!(status.success(), "pnpm failed");
+    ensure!(status.success(), "{pm} failed");
     Ok(())
   }
 
@@ -584,6 +751,7 @@ minify: false,"#,
     let ws_dependencies: Vec<&str> = vec![
       // Building
       "vite",
+      "sass",
 
       // Testing
       "vitest",
@@ -592,19 +760,19 @@ minify: false,"#,
       "typescript@^6",
       "@types/node",
 
-      // Linting and formatting
-      "@biomejs/biome",
-
       // Documentation generation
       "typedoc"
     ];
-
-    self.run_pnpm(|pnpm| {
-      pnpm.args(["add", "--save-dev"]).args(&ws_dependencies);
-      if is_workspace {
-        pnpm.arg("--workspace-root");
-      }
-      pnpm.current_dir(root);
+    let ws_dependencies = ws_dependencies
+      .into_iter()
+      .chain(self.linter().dev_dependencies().iter().copied())
+      .collect::<Vec<_>>();
+
+    let add_args = self
+      .package_manager()
+      .add_args(DependencyKind::Dev, is_workspace);
+    self.run_package_manager(|cmd| {
+      cmd.args(&add_args).args(&ws_dependencies).current_dir(root);
     })?;
 
     Ok(())
@@ -635,6 +803,11 @@ minify: false,"#,
       ..
     } = &self.args;
 
+    ensure!(
+      !self.args.bundle || target.is_lib(),
+      "--bundle can only be used with --target lib"
+    );
+
     let src_dir = root.join("src");
     utils::create_dir(src_dir)?;
 
@@ -650,10 +823,16 @@ minify: false,"#,
     let pkg_config = PackageDepotConfig {
       platform: *platform,
       target: Some(*target),
+      bundle: self.args.bundle.then(BundleConfig::default),
+      linter: self.package_linter_config(),
+      bundler: self.args.bundler,
       ..Default::default()
     };
     let ws_config = WorkspaceDepotConfig {
       depot_version: DEPOT_VERSION.to_string(),
+      package_manager: self.package_manager_config(),
+      linter: self.workspace_linter_config(),
+      ..Default::default()
     };
     let mut config = serde_json::to_value(pkg_config)?;
     json_merge(&mut config, serde_json::to_value(ws_config)?);
@@ -708,6 +887,12 @@ minify: false,"#,
       }
     }
 
+    ensure!(
+      target.is_script() || (target.is_site() && !self.args.vike) || self.args.bundler.is_none(),
+      "Can only choose a bundler when target=script, or target=site without --vike"
+    );
+
+    let mut bundled_exports = None;
     let entry_point = match target {
       Target::Site => {
         ensure!(
@@ -716,6 +901,9 @@ minify: false,"#,
         );
 
         dev_dependencies.push("normalize.css");
+        if self.args.bundler == Some(Bundler::Esbuild) {
+          dev_dependencies.push("esbuild");
+        }
 
         let css_name = if self.args.vike { "base" } else { "index" };
         let css_path = format!("{css_name}.css");
@@ -781,7 +969,11 @@ export default () => {
           manifest.bin = Some(pj::Binary::Object(indexmap! {
             name.name.clone() => format!("dist/{}.cjs", self.args.name)
           }));
-          dev_dependencies.push("vite");
+        }
+        match self.args.bundler.unwrap_or_default() {
+          Bundler::Vite if platform.is_node() => dev_dependencies.push("vite"),
+          Bundler::Vite => {}
+          Bundler::Esbuild => dev_dependencies.push("esbuild"),
         }
         let filename = if self.args.react {
           "main.tsx"
@@ -793,21 +985,43 @@ export default () => {
         Some(filename)
       }
       Target::Lib => {
-        manifest.main = Some(String::from("dist/lib.js"));
         manifest.files = Some(vec![String::from("dist")]);
 
         if self.args.react {
           peer_dependencies.push("react");
         }
 
-        let main_export = pj::ExportsObject::builder()
-          .default("./dist/lib.js")
-          .build();
-        let sub_exports = pj::ExportsObject::builder().default("./dist/*.js").build();
-        manifest.exports = Some(pj::Exports::Nested(indexmap! {
-          ".".into() => main_export,
-          "./*".into() => sub_exports,
-        }));
+        if self.args.bundle {
+          dev_dependencies.push("vite-plugin-dts");
+
+          let formats = BundleConfig::default().formats;
+          let has_cjs = formats.contains(&BundleFormat::Cjs);
+          let has_es = formats.contains(&BundleFormat::Es);
+          manifest.main = Some(String::from(if has_cjs {
+            "dist/lib.cjs"
+          } else {
+            "dist/lib.js"
+          }));
+          manifest.types = Some(String::from(if has_es {
+            "dist/lib.d.ts"
+          } else {
+            "dist/lib.d.cts"
+          }));
+          // A placeholder that keeps the position of `exports` in package.json.
+          manifest.exports = Some(pj::Exports::Path(String::new()));
+          bundled_exports = Some(Self::bundled_exports(&formats));
+        } else {
+          manifest.main = Some(String::from("dist/lib.js"));
+
+          let main_export = pj::ExportsObject::builder()
+            .default("./dist/lib.js")
+            .build();
+          let sub_exports = pj::ExportsObject::builder().default("./dist/*.js").build();
+          manifest.exports = Some(pj::Exports::Nested(indexmap! {
+            ".".into() => main_export,
+            "./*".into() => sub_exports,
+          }));
+        }
 
         files.push(("tests/add.test.ts".into(), TEST.into()));
 
@@ -825,16 +1039,24 @@ export default () => {
 
     manifest.other = Some(other);
 
+    // `package_json_schema` can't represent nested conditions, so they are added after
+    // the manifest is serialized.
+    let mut manifest_json = serde_json::to_value(&manifest)?;
+    if let Some(exports) = bundled_exports {
+      manifest_json["exports"] = exports;
+    }
     files.push((
       "package.json".into(),
-      serde_json::to_string_pretty(&manifest)?.into(),
+      serde_json::to_string_pretty(&manifest_json)?.into(),
     ));
     files.extend(self.make_tsconfig()?);
-    files.extend(self.make_biome_config()?);
+    files.extend(self.make_lint_config()?);
     files.extend(self.make_vite_config(entry_point));
 
     if self.ws_opt.is_none() {
-      files.extend(Self::make_pnpm_workspace(false));
+      if self.package_manager().uses_workspace_file() {
+        files.extend(Self::make_pnpm_workspace(false));
+      }
       files.extend(Self::make_gitignore());
     }
 
@@ -845,18 +1067,20 @@ export default () => {
     }
 
     if !peer_dependencies.is_empty() {
-      self.run_pnpm(|pnpm| {
-        pnpm
-          .args(["add", "--save-peer"])
+      let add_args = self.package_manager().add_args(DependencyKind::Peer, false);
+      self.run_package_manager(|cmd| {
+        cmd
+          .args(&add_args)
           .args(&peer_dependencies)
           .current_dir(root);
       })?;
     }
 
     if !dev_dependencies.is_empty() {
-      self.run_pnpm(|pnpm| {
-        pnpm
-          .args(["add", "--save-dev"])
+      let add_args = self.package_manager().add_args(DependencyKind::Dev, false);
+      self.run_package_manager(|cmd| {
+        cmd
+          .args(&add_args)
           .args(&dev_dependencies)
           .current_dir(root);
       })?;
@@ -875,6 +1099,15 @@ export default () => {
       !(self.ws_opt.is_some() && self.args.workspace),
       "Cannot create a new workspace inside an existing workspace"
     );
+    if let (Some(ws), Some(pm)) = (&self.ws_opt, self.args.package_manager) {
+      ensure!(
+        ws.package_manager == pm,
+        "Cannot use {pm} inside a workspace that uses {}",
+        ws.package_manager
+      );
+    }
+    let pm = self.package_manager();
+    pm.ensure_installed_supported(&ToolVersion::new(pm.find(None)?))?;
 
     let name = &self.args.name;
     let parent_dir = match &self.ws_opt {
@@ -0,0 +1,242 @@
+use std::{fmt, path::PathBuf, process::ExitStatus, time::Duration};
+
+use anyhow::{Context, Result, anyhow, bail, ensure};
+use log::{error, info};
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{DebounceEventResult, Debouncer};
+use package_json_schema as pj;
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use super::build::{BuildArgs, BuildCommand};
+use crate::workspace::{Workspace, package::Package};
+
+/// Build and run a script package
+#[derive(clap::Parser, Default, Debug)]
+pub struct RunArgs {
+  /// Rerun the program after every successful rebuild
+  #[clap(short, long, action)]
+  pub watch: bool,
+
+  /// Arguments to pass to the program
+  #[arg(last = true)]
+  pub args: Vec<String>,
+}
+
+pub struct RunCommand {
+  args: RunArgs,
+}
+
+/// The error when the program run by [`RunCommand`] exits unsuccessfully, so that the
+/// caller can exit with the same code.
+#[derive(Debug)]
+pub struct ProgramFailed(pub ExitStatus);
+
+impl ProgramFailed {
+  /// The exit code of the program, or 1 if it was killed by a signal.
+  pub fn code(&self) -> i32 {
+    self.0.code().unwrap_or(1)
+  }
+}
+
+impl fmt::Display for ProgramFailed {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "Program exited with {}", self.0)
+  }
+}
+
+impl std::error::Error for ProgramFailed {}
+
+/// Watches the files that affect the build of a package and its dependencies.
+struct SourceWatcher {
+  src_dirs: Vec<PathBuf>,
+  rx: UnboundedReceiver<DebounceEventResult>,
+  _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+/// Files at a package root which affect its build. Other files there, like `dist`,
+/// are written by the build itself.
+fn is_build_input(file_name: &str) -> bool {
+  matches!(file_name, "package.json" | "build.mjs" | "tsconfig.json")
+    || file_name.starts_with(".env")
+    || file_name.starts_with("vite.config.")
+}
+
+impl SourceWatcher {
+  fn new(ws: &Workspace, pkg: &Package) -> Result<Self> {
+    let (tx, rx) = unbounded_channel();
+    let timeout = Duration::from_millis(500);
+    let mut debouncer = notify_debouncer_mini::new_debouncer(timeout, move |events| {
+      let _ = tx.send(events);
+    })?;
+
+    let watcher = debouncer.watcher();
+    let mut src_dirs = Vec::new();
+    for pkg in ws.pkg_graph.all_deps_for(pkg).chain([pkg]) {
+      watcher.watch(&pkg.root, RecursiveMode::NonRecursive)?;
+      let src_dir = pkg.root.join("src");
+      if src_dir.exists() {
+        watcher.watch(&src_dir, RecursiveMode::Recursive)?;
+        src_dirs.push(src_dir);
+      }
+    }
+
+    Ok(SourceWatcher {
+      src_dirs,
+      rx,
+      _debouncer: debouncer,
+    })
+  }
+
+  async fn next_change(&mut self) -> Result<()> {
+    loop {
+      let events = self
+        .rx
+        .recv()
+        .await
+        .context("Source watcher stopped")?
+        .map_err(|e| anyhow!("File watch errors: {e:?}"))?;
+      let changed = events.iter().any(|event| {
+        self.src_dirs.iter().any(|dir| event.path.starts_with(dir))
+          || event
+            .path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(is_build_input)
+      });
+      if changed {
+        return Ok(());
+      }
+    }
+  }
+}
+
+impl RunCommand {
+  pub fn new(args: RunArgs) -> Self {
+    RunCommand { args }
+  }
+
+  /// Finds the package to run, which is either the one given by `--package` or the
+  /// only script package in the workspace.
+  fn find_package(ws: &Workspace) -> Result<Package> {
+    let pkg = match &ws.common.package {
+      Some(name) => ws
+        .packages
+        .iter()
+        .find(|pkg| &pkg.name == name)
+        .cloned()
+        .with_context(|| format!("Could not find package with name: {name}"))?,
+      None => {
+        let mut scripts = ws.packages.iter().filter(|pkg| pkg.target.is_script());
+        match (scripts.next(), scripts.next()) {
+          (Some(pkg), None) => pkg.clone(),
+          (None, _) => bail!("Workspace has no script package to run"),
+          (Some(_), Some(_)) => {
+            bail!("Workspace has multiple script packages, choose one with --package")
+          }
+        }
+      }
+    };
+
+    ensure!(
+      pkg.target.is_script() && pkg.platform.is_node(),
+      "Package `{}` is not a node script and cannot be run",
+      pkg.name
+    );
+    Ok(pkg)
+  }
+
+  /// The file to execute, taken from the package's `bin` entry.
+  fn program(pkg: &Package) -> Result<PathBuf> {
+    let path =
+      match &pkg.manifest.manifest.bin {
+        Some(pj::Binary::Path(path)) => path,
+        Some(pj::Binary::Object(bins)) => bins
+          .get(&pkg.name.name)
+          .or_else(|| bins.values().next())
+          .with_context(|| format!("Package `{}` has an empty `bin` entry", pkg.name))?,
+        None => bail!("Package `{}` has no `bin` entry to run", pkg.name),
+      };
+    Ok(pkg.root.join(path))
+  }
+
+  fn spawn(&self, pkg: &Package) -> Result<tokio::process::Child> {
+    let program = Self::program(pkg)?;
+    ensure!(
+      program.exists(),
+      "Build did not produce program: {}",
+      program.display()
+    );
+    let mut cmd = pkg.command("node", |cmd| {
+      cmd.arg(&program).args(&self.args.args);
+    })?;
+    // Unlike the build processes, the program runs where the user invoked Depot.
+    cmd.current_dir(std::env::current_dir()?);
+    cmd.kill_on_drop(true);
+    cmd.spawn().context("Failed to spawn program")
+  }
+
+  async fn build(ws: &Workspace) -> Result<()> {
+    ws.run(BuildCommand::new(BuildArgs::default()).kind()).await
+  }
+
+  pub async fn run(self, ws: Workspace) -> Result<()> {
+    let pkg = Self::find_package(&ws)?;
+
+    // Only build the package being run and its dependencies.
+    let mut ws = if ws.common.package.is_some() {
+      ws
+    } else {
+      let mut common = ws.common.clone();
+      common.package = Some(pkg.name.clone());
+      Workspace::load(Some(ws.root.clone()), common).await?
+    };
+
+    if !self.args.watch {
+      Self::build(&ws).await?;
+      let status = self.spawn(&Self::find_package(&ws)?)?.wait().await?;
+      ensure!(status.success(), ProgramFailed(status));
+      return Ok(());
+    }
+
+    loop {
+      let pkg = Self::find_package(&ws)?;
+      let mut watcher = SourceWatcher::new(&ws, &pkg)?;
+      let mut child = match Self::build(&ws).await {
+        Ok(()) => Some(self.spawn(&pkg)?),
+        Err(e) => {
+          error!("Build failed, waiting for changes: {e:#}");
+          None
+        }
+      };
+
+      loop {
+        let exited = async {
+          match &mut child {
+            Some(child) => child.wait().await,
+            None => std::future::pending().await,
+          }
+        };
+        tokio::select! {
+          change = watcher.next_change() => {
+            change?;
+            break;
+          }
+          status = exited => {
+            let status = status?;
+            if status.success() {
+              info!("Program exited, waiting for changes");
+            } else {
+              error!("{}, waiting for changes", ProgramFailed(status));
+            }
+            child = None;
+          }
+        }
+      }
+
+      if let Some(child) = &mut child {
+        child.kill().await?;
+      }
+      ws = ws.reload().await?;
+    }
+  }
+}
@@ -0,0 +1,66 @@
+use anyhow::Result;
+
+use crate::workspace::{Command, CommandRuntime, CoreCommand, PackageCommand, package::Package};
+
+/// Run a package.json script in every package that defines it
+#[derive(clap::Parser, Debug)]
+pub struct ScriptArgs {
+  /// Name of the script to run
+  pub name: String,
+
+  /// Additional arguments to pass to the script
+  #[arg(last = true)]
+  pub script_args: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ScriptCommand {
+  args: ScriptArgs,
+}
+
+impl ScriptCommand {
+  pub fn new(args: ScriptArgs) -> Self {
+    ScriptCommand { args }
+  }
+
+  pub fn kind(self) -> Command {
+    Command::package(self)
+  }
+}
+
+impl CoreCommand for ScriptCommand {
+  fn name(&self) -> String {
+    // Prefixed so that a script named like a Depot command gets its own fingerprint.
+    format!("script:{}", self.args.name)
+  }
+}
+
+#[async_trait::async_trait]
+impl PackageCommand for ScriptCommand {
+  fn pkg_key(&self, pkg: &Package) -> String {
+    // Runs with different arguments are fingerprinted separately.
+    let key = format!("{}-{}", self.name(), pkg.name);
+    if self.args.script_args.is_empty() {
+      key
+    } else {
+      format!("{key}-{:?}", self.args.script_args)
+    }
+  }
+
+  async fn run_pkg(&self, pkg: &Package) -> Result<()> {
+    if !pkg.has_script(&self.args.name) {
+      return Ok(());
+    }
+
+    let pm = pkg.workspace().package_manager;
+    pkg
+      .exec(pm.name(), |cmd| {
+        cmd.args(pm.run_args(&self.args.name, &self.args.script_args));
+      })
+      .await
+  }
+
+  fn runtime(&self) -> CommandRuntime {
+    CommandRuntime::WaitForDependencies
+  }
+}
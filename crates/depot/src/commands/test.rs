@@ -7,7 +7,7 @@ use anyhow::{Context, Result};
 pub struct TestArgs {
   /// If true, then rerun tests when files change
   #[clap(short, long, action)]
-  watch: bool,
+  pub watch: bool,
 
   /// Additional arguments to pass to vitest
   #[arg(last = true)]
@@ -37,18 +37,27 @@ impl PackageCommand for TestCommand {
       None => None,
     };
 
-    pkg
-      .exec("vitest", |cmd| {
-        let subcmd = if self.args.watch { "watch" } else { "run" };
-        cmd.arg(subcmd);
+    let process = pkg.start_process("vitest", |cmd| {
+      let subcmd = if self.args.watch { "watch" } else { "run" };
+      cmd.arg(subcmd);
 
-        cmd.arg("--passWithNoTests");
+      cmd.arg("--passWithNoTests");
+      // Vitest defaults to the `test` mode, so only override it if a profile was chosen.
+      let ws = pkg.workspace();
+      if ws.common.profile.is_some() {
+        cmd.args(["--mode", &ws.profile.mode]);
+      }
 
-        if let Some(vitest_args) = vitest_args {
-          cmd.args(vitest_args);
-        }
-      })
-      .await
+      if let Some(vitest_args) = vitest_args {
+        cmd.args(vitest_args);
+      }
+    })?;
+
+    if self.args.watch {
+      process.supervise(pkg.workspace().max_restarts()).await
+    } else {
+      process.wait_for_success().await
+    }
   }
 
   fn deps(&self) -> Vec<Command> {
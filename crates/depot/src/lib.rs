@@ -1,5 +1,38 @@
+//! Depot is a Javascript devtool orchestrator. Besides the `depot` CLI, this crate can
+//! be used as a library to run Depot's commands from another tool.
+//!
+//! A [`Workspace`] is loaded from a directory with [`CommonArgs`].
+//! Built-in commands are created from their arguments, like
+//! [`BuildCommand::new(args).kind()`](commands::build::BuildCommand), and new commands
+//! implement [`PackageCommand`](workspace::PackageCommand) or
+//! [`WorkspaceCommand`](workspace::WorkspaceCommand). A command is run with its
+//! dependencies by [`Workspace::run_with_renderer`](workspace::Workspace::run_with_renderer),
+//! which reports the outcome of each task.
+//!
+//! ```no_run
+//! use depot_js::{
+//!   CommonArgs,
+//!   commands::build::{BuildArgs, BuildCommand},
+//!   logger::ui::PlainRenderer,
+//!   workspace::Workspace,
+//! };
+//!
+//! # async fn example() -> anyhow::Result<()> {
+//! let ws = Workspace::load(Some("path/to/workspace".into()), CommonArgs::default()).await?;
+//! let build = BuildCommand::new(BuildArgs::default()).kind();
+//! let report = ws.run_with_renderer(build, PlainRenderer::new()).await?;
+//! for task in report.tasks.iter().filter(|task| task.error.is_some()) {
+//!   println!("{} failed", task.key);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
 #![warn(clippy::pedantic)]
 #![allow(
+  clippy::missing_errors_doc,
+  clippy::missing_panics_doc,
+  clippy::must_use_candidate,
   clippy::format_collect,
   clippy::similar_names,
   clippy::module_name_repetitions,
@@ -8,36 +41,65 @@
 )]
 
 use self::commands::Command;
-use anyhow::{Result, bail};
+use anyhow::{Result, bail, ensure};
 use clap::Parser;
 use commands::{
-  build::BuildCommand, clean::CleanCommand, doc::DocCommand, fix::FixCommand, fmt::FmtCommand,
-  init::InitCommand, new::NewCommand, test::TestCommand,
+  build::BuildCommand, clean::CleanCommand, custom::CustomCommand, doc::DocCommand,
+  doctor::DoctorCommand, fix::FixCommand, fmt::FmtCommand, init::InitCommand, new::NewCommand,
+  run::RunCommand, script::ScriptCommand, test::TestCommand,
+};
+use logger::ui::Progress;
+use std::ffi::OsString;
+use workspace::{
+  Workspace,
+  engines::{self, ToolVersion},
+  package::PackageName,
+  profile::RELEASE_PROFILE,
 };
-use workspace::{Workspace, package::PackageName};
 
-mod commands;
-mod logger;
+pub mod commands;
+pub mod logger;
 mod utils;
-mod workspace;
+pub mod workspace;
 
-#[derive(clap::Parser, Default)]
+#[derive(clap::Parser, Clone, Default)]
+#[allow(clippy::struct_excessive_bools)]
 pub struct CommonArgs {
   /// Only run the command for a given package and its dependencies
   #[clap(short, long)]
-  package: Option<PackageName>,
+  pub package: Option<PackageName>,
 
   /// Enable incremental compilation
   #[clap(long)]
-  incremental: bool,
+  pub incremental: bool,
 
   /// Disable fullscreen UI
   #[clap(long)]
-  no_fullscreen: bool,
+  pub no_fullscreen: bool,
+
+  /// How to display process output
+  #[clap(long, value_enum, default_value_t = Progress::Auto)]
+  pub progress: Progress,
+
+  /// Build profile to use, either `dev`, `release`, or one from the workspace manifest
+  #[clap(long)]
+  pub profile: Option<String>,
+
+  /// Run only the given command, without the commands it depends on
+  #[clap(long)]
+  pub no_deps: bool,
+
+  /// Keep running tasks that don't depend on a failed task
+  #[clap(long)]
+  pub keep_going: bool,
 
   /// Don't attempt to interact with the web
   #[arg(long, action)]
   pub offline: bool,
+
+  /// Don't check the versions of node and the package manager
+  #[clap(long)]
+  pub ignore_engines: bool,
 }
 
 #[derive(clap::Parser)]
@@ -50,9 +112,23 @@ struct Args {
   common: CommonArgs,
 }
 
-#[allow(clippy::missing_errors_doc)]
+/// Runs Depot with the arguments of the current process.
 pub async fn run() -> Result<()> {
-  let Args { command, common } = Args::parse();
+  run_from(std::env::args_os()).await
+}
+
+/// Runs Depot with the CLI arguments `args`, where the first is the program name.
+pub async fn run_from(args: impl IntoIterator<Item = impl Into<OsString> + Clone>) -> Result<()> {
+  let Args {
+    command,
+    mut common,
+  } = Args::parse_from(args);
+
+  // The doctor reports missing tools itself.
+  let command = match command {
+    Command::Doctor(args) => return DoctorCommand::new(args).run(),
+    command => command,
+  };
 
   if utils::find_node().is_none() {
     bail!(
@@ -60,29 +136,56 @@ pub async fn run() -> Result<()> {
     );
   }
 
-  if utils::find_pnpm(None).is_none() {
-    bail!(
-      "Failed to find `pnpm` installed on your path. Depot requires pnpm to be installed. See: https://pnpm.io/installation"
-    )
-  }
-
   let command = match command {
     Command::New(args) => return NewCommand::new(args).await.run(),
     command => command,
   };
 
+  if let Command::Build(args) = &command
+    && args.release
+  {
+    ensure!(
+      common
+        .profile
+        .as_deref()
+        .is_none_or(|p| p == RELEASE_PROFILE),
+      "--release cannot be combined with --profile"
+    );
+    common.profile = Some(RELEASE_PROFILE.into());
+  }
+
   let ws = Workspace::load(None, common).await?;
+  // Shared by both checks, so the package manager is run at most once.
+  let pm_version = ToolVersion::new(ws.package_manager.find(Some(&ws.root))?);
+  ws.package_manager.ensure_installed_supported(&pm_version)?;
+  if !ws.common.ignore_engines {
+    engines::check(&ws, &pm_version)?;
+  }
+
+  let command = match command {
+    Command::Run(args) => return RunCommand::new(args).run(ws).await,
+    command => command,
+  };
 
   // TODO: merge all tasks into a single task graph like Cargo
   let command = match command {
     Command::Init(args) => InitCommand::new(args).kind(),
+    Command::Custom(args) => CustomCommand::from_cli(&ws, args)?,
     Command::Build(args) => BuildCommand::new(args).kind(),
     Command::Test(args) => TestCommand::new(args).kind(),
     Command::Fmt(args) => FmtCommand::new(args).kind(),
     Command::Clean(args) => CleanCommand::new(args).kind(),
     Command::Doc(args) => DocCommand::new(args).kind(),
     Command::Fix(args) => FixCommand::new(args).kind(),
-    Command::New(..) => unreachable!(),
+    Command::Script(args) => {
+      ensure!(
+        ws.pkg_graph.nodes().any(|pkg| pkg.has_script(&args.name)),
+        "No package defines the script `{}`",
+        args.name
+      );
+      ScriptCommand::new(args).kind()
+    }
+    Command::New(..) | Command::Run(..) | Command::Doctor(..) => unreachable!(),
   };
 
   ws.run(command).await?;
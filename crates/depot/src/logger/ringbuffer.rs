@@ -3,16 +3,33 @@ use std::collections::{VecDeque, vec_deque};
 pub struct RingBuffer<T> {
   data: VecDeque<T>,
   max_capacity: usize,
+  total_pushed: usize,
 }
 
 const DEFAULT_MAX_CAPACITY: usize = 1024;
 
+impl<T> Default for RingBuffer<T> {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl<'a, T> IntoIterator for &'a RingBuffer<T> {
+  type Item = &'a T;
+  type IntoIter = vec_deque::Iter<'a, T>;
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
 #[allow(unused)]
 impl<T> RingBuffer<T> {
   pub fn new() -> Self {
     RingBuffer {
       data: VecDeque::new(),
       max_capacity: DEFAULT_MAX_CAPACITY,
+      total_pushed: 0,
     }
   }
 
@@ -20,6 +37,7 @@ impl<T> RingBuffer<T> {
     RingBuffer {
       data: VecDeque::new(),
       max_capacity,
+      total_pushed: 0,
     }
   }
 
@@ -28,6 +46,7 @@ impl<T> RingBuffer<T> {
       self.data.pop_front();
     }
     self.data.push_back(log);
+    self.total_pushed += 1;
   }
 
   pub fn iter(&self) -> vec_deque::Iter<'_, T> {
@@ -41,6 +60,21 @@ impl<T> RingBuffer<T> {
   pub fn len(&self) -> usize {
     self.data.len()
   }
+
+  pub fn is_empty(&self) -> bool {
+    self.data.is_empty()
+  }
+
+  /// Number of elements ever pushed, including ones that were evicted or cleared.
+  pub fn total_pushed(&self) -> usize {
+    self.total_pushed
+  }
+
+  /// Iterates over the retained elements whose push index is at least `start`.
+  pub fn iter_since(&self, start: usize) -> impl Iterator<Item = &T> {
+    let first_retained = self.total_pushed - self.data.len();
+    self.data.iter().skip(start.saturating_sub(first_retained))
+  }
 }
 
 #[test]
@@ -74,5 +108,19 @@ fn test_log_buffer() {
   assert_eq!(contents!(), vec![3, 4, 5, 6]);
 
   extend!([7, 8, 9, 10, 11]);
-  assert_eq!(contents!(), vec![8, 9, 10, 11])
+  assert_eq!(contents!(), vec![8, 9, 10, 11]);
+
+  assert_eq!(buffer.total_pushed(), 12);
+  assert_eq!(
+    buffer.iter_since(10).copied().collect::<Vec<_>>(),
+    vec![10, 11]
+  );
+  assert_eq!(
+    buffer.iter_since(0).copied().collect::<Vec<_>>(),
+    vec![8, 9, 10, 11]
+  );
+
+  buffer.clear();
+  extend!([12]);
+  assert_eq!(buffer.iter_since(12).copied().collect::<Vec<_>>(), vec![12]);
 }
@@ -1,41 +1,232 @@
 use ansi_to_tui::IntoText;
 use anyhow::{Context, Result};
 use crossterm::{
-  event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+  event::{
+    DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton,
+    MouseEvent, MouseEventKind,
+  },
   execute,
   style::{Color, ResetColor, SetForegroundColor},
   terminal::{EnterAlternateScreen, LeaveAlternateScreen},
 };
 use futures::StreamExt;
 use ratatui::{
-  layout::{Constraint, Direction, Layout},
+  layout::{Constraint, Layout, Position},
   prelude::Rect,
-  style::{Modifier, Style},
+  style::{Style, Stylize},
   text::{Line, Span, Text},
-  widgets::{Block, Borders, Paragraph, Tabs, Wrap},
+  widgets::{Block, Borders, Paragraph, Wrap},
 };
 use std::{
-  io::{Stdout, Write},
+  borrow::Cow,
+  collections::{HashMap, hash_map::Entry},
+  env,
+  io::{IsTerminal, Stdout, Write},
+  iter,
   sync::{
-    Arc, Mutex,
+    Arc, Mutex, RwLockReadGuard,
     atomic::{AtomicIsize, Ordering},
   },
   time::Duration,
 };
-use tokio::sync::Notify;
+use tokio::sync::{Notify, watch};
+
+use crate::workspace::{
+  Task, TaskStatus, Workspace,
+  package::PackageIndex,
+  process::{Process, ProcessStatus},
+};
+
+/// How Depot displays the output of running processes.
+#[derive(Copy, Clone, Default, clap::ValueEnum)]
+pub enum Progress {
+  /// Interactive display if stdout is a terminal, plain output otherwise
+  #[default]
+  Auto,
+  /// Always use the interactive display
+  Tty,
+  /// Stream each line of output once, prefixed with its package and process
+  Plain,
+}
+
+impl Progress {
+  pub fn is_plain(self) -> bool {
+    match self {
+      Progress::Auto => !std::io::stdout().is_terminal(),
+      Progress::Tty => false,
+      Progress::Plain => true,
+    }
+  }
+}
+
+/// Removes ANSI escape sequences (colors, cursor movement, etc.) from a string.
+pub fn strip_ansi(s: &str) -> Cow<'_, str> {
+  if !s.contains('\u{1b}') {
+    return Cow::Borrowed(s);
+  }
+
+  let mut output = String::with_capacity(s.len());
+  let mut chars = s.chars();
+  while let Some(c) = chars.next() {
+    if c != '\u{1b}' {
+      output.push(c);
+      continue;
+    }
+
+    match chars.next() {
+      // Control sequence: ends with a byte in the range @ to ~
+      Some('[') => {
+        for c in chars.by_ref() {
+          if ('@'..='~').contains(&c) {
+            break;
+          }
+        }
+      }
+      // Operating system command: ends with BEL or ESC \
+      Some(']') => {
+        while let Some(c) = chars.next() {
+          if c == '\u{7}' {
+            break;
+          } else if c == '\u{1b}' {
+            chars.next();
+            break;
+          }
+        }
+      }
+      _ => {}
+    }
+  }
 
-use crate::workspace::{Workspace, process::Process};
+  Cow::Owned(output)
+}
 
 pub struct FullscreenRenderer {
   terminal: Mutex<Terminal>,
   selected: AtomicIsize,
+  view: Mutex<ViewState>,
+}
+
+/// A group of processes that can be selected in the fullscreen UI.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Selection {
+  Workspace,
+  Package(PackageIndex),
+}
+
+impl Selection {
+  /// All selectable groups in display order, starting with the workspace if it has any tasks.
+  fn all(ws: &Workspace) -> Vec<Selection> {
+    let has_ws_tasks = ws.tasks().iter().any(|task| task.package().is_none());
+    let ws_selection = (has_ws_tasks || !ws.processes().is_empty()).then_some(Selection::Workspace);
+    ws_selection
+      .into_iter()
+      .chain(
+        ws.package_display_order()
+          .map(|pkg| Selection::Package(pkg.index)),
+      )
+      .collect()
+  }
+
+  fn name(self, ws: &Workspace) -> String {
+    match self {
+      Selection::Workspace => "workspace".into(),
+      Selection::Package(index) => ws.packages[index].name.to_string(),
+    }
+  }
+
+  fn processes(self, ws: &Workspace) -> RwLockReadGuard<'_, Vec<Arc<Process>>> {
+    match self {
+      Selection::Workspace => ws.processes(),
+      Selection::Package(index) => ws.packages[index].processes(),
+    }
+  }
+
+  fn owns(self, task: &Task) -> bool {
+    match (self, task.package()) {
+      (Selection::Workspace, None) => true,
+      (Selection::Package(index), Some(pkg)) => pkg.index == index,
+      _ => false,
+    }
+  }
+}
+
+/// Identifies a process pane by its group and its position within that group.
+type PaneId = (Selection, usize);
+
+/// Interactive state of the fullscreen UI, besides the selected group.
+#[derive(Default)]
+struct ViewState {
+  /// Index of the focused process within the selected group.
+  focused: usize,
+
+  /// If true, the focused process fills the whole log area.
+  zoomed: bool,
+
+  /// Number of lines that each pane is scrolled up from the bottom.
+  scroll: HashMap<PaneId, usize>,
+
+  /// Where each pane was drawn in the last frame, used to map mouse events to panes.
+  panes: Vec<(PaneId, Rect)>,
+
+  /// Where each sidebar row was drawn in the last frame, with the index of its selection.
+  sidebar: Vec<(Rect, usize)>,
+
+  search: Option<Search>,
+}
+
+struct Search {
+  query: String,
+
+  /// True while the query is still being typed.
+  editing: bool,
+
+  /// Position of the selected match, as counted by [`RingBuffer::total_pushed`].
+  ///
+  /// [`RingBuffer::total_pushed`]: crate::logger::ringbuffer::RingBuffer::total_pushed
+  current: Option<usize>,
 }
 
 const TICK_RATE: Duration = Duration::from_millis(33);
+const MOUSE_SCROLL_LINES: usize = 3;
+const DEFAULT_PAGE_SIZE: usize = 10;
+const MIN_PANE_WIDTH: u16 = 40;
+const MIN_PANE_HEIGHT: u16 = 8;
+const MAX_SIDEBAR_WIDTH: u16 = 32;
 
 pub type TerminalBackend = ratatui::backend::CrosstermBackend<Stdout>;
 pub type Terminal = ratatui::Terminal<TerminalBackend>;
 
+/// Returns the number of columns and rows used to tile `n` panes, given the
+/// maximum number of columns and rows that fit on the screen.
+fn grid_dims(n: usize, max_cols: usize, max_rows: usize) -> (usize, usize) {
+  let n = n.clamp(1, max_cols * max_rows);
+  let mut cols = 1;
+  while cols * cols < n {
+    cols += 1;
+  }
+  let cols = cols.max(n.div_ceil(max_rows)).min(max_cols);
+  (cols, n.div_ceil(cols))
+}
+
+fn ratios(n: usize) -> Vec<Constraint> {
+  let n = u32::try_from(n).unwrap();
+  (0..n).map(|_| Constraint::Ratio(1, n)).collect()
+}
+
+/// Splits `area` into a grid of `n` panes, where the last row may contain fewer panes.
+fn tile(area: Rect, n: usize, max_cols: usize, max_rows: usize) -> Vec<Rect> {
+  let (cols, rows) = grid_dims(n, max_cols, max_rows);
+  let row_areas = Layout::vertical(ratios(rows)).split(area);
+  row_areas
+    .iter()
+    .enumerate()
+    .flat_map(|(row, row_area)| {
+      let in_row = n.saturating_sub(row * cols).min(cols);
+      Layout::horizontal(ratios(in_row)).split(*row_area).to_vec()
+    })
+    .collect()
+}
+
 impl FullscreenRenderer {
   pub fn new() -> Result<Self> {
     let stdout = std::io::stdout();
@@ -53,33 +244,120 @@ impl FullscreenRenderer {
     Ok(FullscreenRenderer {
       terminal: Mutex::new(terminal),
       selected: AtomicIsize::new(0),
+      view: Mutex::default(),
     })
   }
 
-  fn build_tabs(ws: &Workspace, selected: usize) -> Option<Tabs<'_>> {
-    ws.monorepo.then(|| {
-      let titles = ws
-        .package_display_order()
-        .enumerate()
-        .map(|(i, pkg)| {
-          let pkg_name = pkg.name.to_string();
-          let mut style = Style::default();
-          if i == selected {
-            style = style.add_modifier(Modifier::BOLD);
-          }
-          Span::styled(pkg_name, style)
-        })
-        .collect::<Vec<_>>();
-      Tabs::new(titles)
-    })
+  /// Returns all selectable groups and the index of the selected one.
+  fn selection(&self, ws: &Workspace) -> (Vec<Selection>, usize) {
+    let selections = Selection::all(ws);
+    let n = isize::try_from(selections.len()).unwrap();
+    let selected_unbounded = self.selected.load(Ordering::SeqCst);
+    let selected = usize::try_from((n + selected_unbounded % n) % n).unwrap();
+    (selections, selected)
+  }
+
+  fn build_sidebar(
+    ws: &Workspace,
+    selections: &[Selection],
+    selected: usize,
+  ) -> (Vec<Line<'static>>, Vec<usize>) {
+    let tasks = ws.tasks();
+    let mut lines = Vec::new();
+    let mut rows = Vec::new();
+    for (i, selection) in selections.iter().enumerate() {
+      let mut style = Style::new().bold();
+      if i == selected {
+        style = style.reversed();
+      }
+      lines.push(Line::styled(selection.name(ws), style));
+      rows.push(i);
+
+      for task in tasks.iter().filter(|task| selection.owns(task)) {
+        let (symbol, style) = match task.status() {
+          TaskStatus::Pending => ("○", Style::new().dark_gray()),
+          TaskStatus::Running => ("●", Style::new().yellow()),
+          TaskStatus::Finished => ("✓", Style::new().green()),
+          TaskStatus::Failed => ("✗", Style::new().red()),
+        };
+        let label = format!("  {symbol} {}", task.command().name());
+        lines.push(Line::styled(label, style));
+        rows.push(i);
+      }
+    }
+    (lines, rows)
   }
 
-  fn render_process_pane(f: &mut ratatui::Frame, process: &Process, slot: Rect) {
+  fn build_status_bar(search: Option<&Search>, page: usize, pages: usize) -> Paragraph<'_> {
     let mut spans = Vec::new();
-    let height = slot.bottom() as usize;
+    match search {
+      Some(search) => {
+        spans.extend([Span::raw("/"), Span::raw(search.query.clone())]);
+        if !search.editing {
+          spans.push(Span::raw("  (n: older match, N: newer match, Esc: clear)").dim());
+        }
+      }
+      None => {
+        if pages > 1 {
+          spans.push(Span::raw(format!("page {}/{pages}  ", page + 1)));
+        }
+        spans
+          .push(Span::raw("←/→: select  Tab: focus  PgUp/PgDn: scroll  z: zoom  /: search").dim());
+      }
+    }
+    Paragraph::new(Line::from(spans))
+  }
+
+  fn highlight_matches(text: &str, query: &str, current: bool) -> Line<'static> {
+    let match_style = if current {
+      Style::new().black().on_light_red()
+    } else {
+      Style::new().black().on_yellow()
+    };
+    let mut spans = Vec::new();
+    let mut rest = text;
+    while let Some(pos) = rest.find(query) {
+      spans.push(Span::raw(rest[..pos].to_string()));
+      spans.push(Span::styled(
+        rest[pos..pos + query.len()].to_string(),
+        match_style,
+      ));
+      rest = &rest[pos + query.len()..];
+    }
+    spans.push(Span::raw(rest.to_string()));
+    Line::from(spans)
+  }
+
+  fn render_process_pane(
+    f: &mut ratatui::Frame,
+    process: &Process,
+    slot: Rect,
+    scroll: &mut usize,
+    focused: bool,
+    search: Option<&Search>,
+  ) {
+    let height = usize::from(slot.height.saturating_sub(2));
     let stdout = process.stdout();
-    let last_lines = stdout.iter().rev().take(height).rev();
-    for line in last_lines {
+    *scroll = (*scroll).min(stdout.len().saturating_sub(height));
+    let end = stdout.len() - *scroll;
+    let start = end.saturating_sub(height);
+    let first_position = stdout.total_pushed() - stdout.len();
+    let query = search
+      .map(|search| search.query.as_str())
+      .filter(|query| !query.is_empty());
+    let current_match = search.and_then(|search| search.current);
+
+    let mut spans = Vec::new();
+    for (i, line) in stdout.iter().enumerate().take(end).skip(start) {
+      if let Some(query) = query {
+        let plain = strip_ansi(&line.line);
+        if plain.contains(query) {
+          let current = current_match == Some(first_position + i);
+          spans.push(Self::highlight_matches(&plain, query, current));
+          continue;
+        }
+      }
+
       // TODO: distinguish stdout from stderr
       match line.line.into_text() {
         Ok(text) => spans.extend(text.lines),
@@ -88,63 +366,283 @@ impl FullscreenRenderer {
         )))),
       }
     }
+
+    let crashed = process.status() == ProcessStatus::Crashed;
+    let mut title = process.script().to_string();
+    if crashed {
+      title.push_str(" (crashed, restarting)");
+    }
+    if *scroll > 0 {
+      title = format!("{title} [{} more below]", *scroll);
+    }
+    let border_style = if crashed {
+      Style::new().red()
+    } else if focused {
+      Style::new().cyan()
+    } else {
+      Style::new()
+    };
     let p = Paragraph::new(Text::from(spans))
       .block(
         Block::default()
-          .title(process.script())
-          .borders(Borders::ALL),
+          .title(title)
+          .borders(Borders::ALL)
+          .border_style(border_style),
       )
       .wrap(Wrap { trim: false });
     f.render_widget(p, slot);
   }
+
+  fn pane_height(view: &ViewState, id: PaneId) -> usize {
+    view
+      .panes
+      .iter()
+      .find(|(pane, _)| *pane == id)
+      .map_or(DEFAULT_PAGE_SIZE, |(_, rect)| {
+        usize::from(rect.height.saturating_sub(2))
+      })
+  }
+
+  /// Selects the closest match that is older (or newer) than the current one
+  /// in the focused process, and scrolls so the match is visible.
+  fn jump_to_match(&self, ws: &Workspace, view: &mut ViewState, older: bool) {
+    let Some(search) = &mut view.search else {
+      return;
+    };
+    if search.query.is_empty() {
+      return;
+    }
+
+    let (selections, selected) = self.selection(ws);
+    let selection = selections[selected];
+    let processes = selection.processes(ws);
+    let Some(process) = processes.get(view.focused) else {
+      return;
+    };
+
+    let stdout = process.stdout();
+    let first_position = stdout.total_pushed() - stdout.len();
+    let mut matches = stdout
+      .iter()
+      .enumerate()
+      .filter(|(_, line)| strip_ansi(&line.line).contains(&search.query))
+      .map(|(i, _)| first_position + i);
+    let target = match search.current {
+      None => matches.next_back(),
+      Some(current) if older => matches.take_while(|i| *i < current).last(),
+      Some(current) => matches.find(|i| *i > current),
+    };
+
+    if let Some(target) = target {
+      search.current = Some(target);
+      let id = (selection, view.focused);
+      let from_bottom = stdout.total_pushed() - 1 - target;
+      let scroll = from_bottom.saturating_sub(Self::pane_height(view, id) / 2);
+      view.scroll.insert(id, scroll);
+    }
+  }
+
+  fn restart(process: &Arc<Process>) {
+    if let Err(e) = process.restart() {
+      process.push_line(format!("Failed to restart: {e:?}"));
+    }
+  }
+
+  fn scroll_pane(view: &mut ViewState, id: PaneId, up: bool, lines: usize) {
+    let scroll = view.scroll.entry(id).or_default();
+    *scroll = if up {
+      scroll.saturating_add(lines)
+    } else {
+      scroll.saturating_sub(lines)
+    };
+  }
+
+  fn handle_key(&self, ws: &Workspace, key: KeyEvent) {
+    let mut view = self.view.lock().unwrap();
+    let view = &mut *view;
+
+    if let Some(search) = view.search.as_mut().filter(|search| search.editing) {
+      match key.code {
+        KeyCode::Char(c) => search.query.push(c),
+        KeyCode::Backspace => {
+          search.query.pop();
+        }
+        KeyCode::Enter => {
+          search.editing = false;
+          self.jump_to_match(ws, view, true);
+        }
+        KeyCode::Esc => view.search = None,
+        _ => {}
+      }
+      return;
+    }
+
+    let (selections, selected) = self.selection(ws);
+    let selection = selections[selected];
+    let num_processes = selection.processes(ws).len().max(1);
+    let focused_id = (selection, view.focused);
+    let page_size = Self::pane_height(view, focused_id);
+
+    match key.code {
+      KeyCode::Left => {
+        self.selected.fetch_sub(1, Ordering::SeqCst);
+        view.focused = 0;
+      }
+      KeyCode::Right => {
+        self.selected.fetch_add(1, Ordering::SeqCst);
+        view.focused = 0;
+      }
+      KeyCode::Tab => view.focused = (view.focused + 1) % num_processes,
+      KeyCode::BackTab => view.focused = (view.focused + num_processes - 1) % num_processes,
+      KeyCode::PageUp => Self::scroll_pane(view, focused_id, true, page_size),
+      KeyCode::PageDown => Self::scroll_pane(view, focused_id, false, page_size),
+      KeyCode::Home => Self::scroll_pane(view, focused_id, true, usize::MAX),
+      KeyCode::End => Self::scroll_pane(view, focused_id, false, usize::MAX),
+      KeyCode::Char('z') => view.zoomed = !view.zoomed,
+      KeyCode::Char('/') => {
+        view.search = Some(Search {
+          query: String::new(),
+          editing: true,
+          current: None,
+        });
+      }
+      KeyCode::Char('r') => {
+        if let Some(process) = selection.processes(ws).get(view.focused) {
+          Self::restart(process);
+        }
+      }
+      KeyCode::Char('R') => {
+        for process in selection.processes(ws).iter() {
+          Self::restart(process);
+        }
+      }
+      KeyCode::Char('t') => {
+        let ws_processes = ws.processes();
+        let pkg_processes = ws.packages.iter().map(|pkg| pkg.processes());
+        for processes in iter::once(ws_processes).chain(pkg_processes) {
+          for process in processes.iter().filter(|p| p.script() == "vitest") {
+            Self::restart(process);
+          }
+        }
+      }
+      KeyCode::Char('n') => self.jump_to_match(ws, view, true),
+      KeyCode::Char('N') => self.jump_to_match(ws, view, false),
+      KeyCode::Esc => view.search = None,
+      _ => {}
+    }
+  }
+
+  fn handle_mouse(&self, mouse: MouseEvent) {
+    let mut view = self.view.lock().unwrap();
+    let position = Position::new(mouse.column, mouse.row);
+
+    if let MouseEventKind::Down(MouseButton::Left) = mouse.kind {
+      let row = view
+        .sidebar
+        .iter()
+        .find(|(rect, _)| rect.contains(position));
+      if let Some((_, selection)) = row {
+        self
+          .selected
+          .store(isize::try_from(*selection).unwrap(), Ordering::SeqCst);
+        view.focused = 0;
+        return;
+      }
+    }
+
+    let Some(id) = view
+      .panes
+      .iter()
+      .find(|(_, rect)| rect.contains(position))
+      .map(|(id, _)| *id)
+    else {
+      return;
+    };
+
+    match mouse.kind {
+      MouseEventKind::ScrollUp => Self::scroll_pane(&mut view, id, true, MOUSE_SCROLL_LINES),
+      MouseEventKind::ScrollDown => Self::scroll_pane(&mut view, id, false, MOUSE_SCROLL_LINES),
+      MouseEventKind::Down(MouseButton::Left) => view.focused = id.1,
+      _ => {}
+    }
+  }
 }
 
 #[async_trait::async_trait]
 impl Renderer for FullscreenRenderer {
   fn render(&self, ws: &Workspace) -> Result<()> {
-    let n = isize::try_from(ws.pkg_graph.nodes().count()).unwrap();
-    let selected_unbounded = self.selected.load(Ordering::SeqCst);
-    let selected = usize::try_from((n + selected_unbounded % n) % n).unwrap();
-    let pkg = ws.package_display_order().nth(selected).unwrap();
-    let processes = pkg.processes();
+    let (selections, selected) = self.selection(ws);
+    let selection = selections[selected];
+    let processes = selection.processes(ws);
+    let (sidebar_lines, sidebar_rows) = Self::build_sidebar(ws, &selections, selected);
 
-    let tabs = Self::build_tabs(ws, selected);
+    let mut view = self.view.lock().unwrap();
+    let view = &mut *view;
+    view.focused = view.focused.min(processes.len().saturating_sub(1));
 
     let mut terminal = self.terminal.lock().unwrap();
     terminal.draw(|f| {
-      let size = f.area();
-      let constraints = if tabs.is_some() {
-        vec![Constraint::Min(0), Constraint::Length(2)]
+      let [main, status_bar] =
+        Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(f.area());
+
+      let sidebar_width = sidebar_lines
+        .iter()
+        .map(Line::width)
+        .max()
+        .map_or(0, |width| u16::try_from(width + 2).unwrap_or(u16::MAX))
+        .min(MAX_SIDEBAR_WIDTH);
+      let [sidebar, logs] =
+        Layout::horizontal([Constraint::Length(sidebar_width), Constraint::Min(0)]).areas(main);
+
+      view.sidebar = sidebar_rows
+        .into_iter()
+        .zip(sidebar.rows())
+        .map(|(selection, row)| (row, selection))
+        .collect();
+      let sidebar_widget =
+        Paragraph::new(Text::from(sidebar_lines)).block(Block::default().borders(Borders::RIGHT));
+      f.render_widget(sidebar_widget, sidebar);
+
+      let max_cols = usize::from((logs.width / MIN_PANE_WIDTH).max(1));
+      let max_rows = usize::from((logs.height / MIN_PANE_HEIGHT).max(1));
+      let per_page = max_cols * max_rows;
+      let page = view.focused / per_page;
+      let pages = processes.len().div_ceil(per_page).max(1);
+
+      let log_slots = if view.zoomed {
+        vec![(view.focused, logs)]
       } else {
-        vec![Constraint::Min(0)]
+        let first = page * per_page;
+        let on_page = processes.len().saturating_sub(first).min(per_page);
+        tile(logs, on_page, max_cols, max_rows)
+          .into_iter()
+          .enumerate()
+          .map(|(i, slot)| (first + i, slot))
+          .collect::<Vec<_>>()
       };
-      let canvas = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(constraints)
-        .split(size);
 
-      if let Some(tabs) = tabs {
-        f.render_widget(tabs, canvas[1]);
+      view.panes.clear();
+      for (i, slot) in log_slots {
+        let Some(process) = processes.get(i) else {
+          continue;
+        };
+        let id = (selection, i);
+        let scroll = view.scroll.entry(id).or_default();
+        Self::render_process_pane(
+          f,
+          process,
+          slot,
+          scroll,
+          i == view.focused,
+          view.search.as_ref(),
+        );
+        view.panes.push((id, slot));
       }
 
-      let log_halves = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([Constraint::Ratio(7, 10), Constraint::Ratio(3, 10)])
-        .split(canvas[0]);
-      let log_slots = log_halves
-        .iter()
-        .flat_map(|half| {
-          Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
-            .split(*half)
-            .to_vec()
-        })
-        .collect::<Vec<_>>();
-
-      for (process, slot) in processes.iter().zip(log_slots) {
-        Self::render_process_pane(f, process, slot);
-      }
+      f.render_widget(
+        Self::build_status_bar(view.search.as_ref(), page, pages),
+        status_bar,
+      );
     })?;
 
     Ok(())
@@ -153,20 +651,19 @@ impl Renderer for FullscreenRenderer {
   // TODO: This still occasionally drops inputs, seems to conflict with async-process.
   // See the note on `crossterm` dependency in Cargo.toml.
   // Maybe we should try to spawn this future in a separate thread?
-  async fn handle_input(&self) -> Result<bool> {
+  async fn handle_input(&self, ws: &watch::Receiver<Workspace>) -> Result<bool> {
     let mut reader = crossterm::event::EventStream::new();
     while let Some(event) = reader.next().await {
-      if let Event::Key(key) = event? {
-        match key.code {
-          KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(true),
-          KeyCode::Left => {
-            self.selected.fetch_sub(1, Ordering::SeqCst);
-          }
-          KeyCode::Right => {
-            self.selected.fetch_add(1, Ordering::SeqCst);
+      match event? {
+        Event::Key(key) => {
+          if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return Ok(true);
           }
-          _ => {}
+          let ws = ws.borrow().clone();
+          self.handle_key(&ws, key);
         }
+        Event::Mouse(mouse) => self.handle_mouse(mouse),
+        _ => {}
       }
     }
     Ok(false)
@@ -195,22 +692,29 @@ pub trait Renderer: Sized + Send + Sync {
   fn render(&self, ws: &Workspace) -> Result<()>;
   fn complete(self, ws: &Workspace) -> Result<()>;
 
-  async fn handle_input(&self) -> Result<bool> {
-    loop {
-      tokio::time::sleep(Duration::MAX).await;
-    }
+  /// Exits on Ctrl-C. Processes run in their own process groups, so the terminal doesn't
+  /// interrupt them, and they are killed when Depot exits instead.
+  async fn handle_input(&self, _ws: &watch::Receiver<Workspace>) -> Result<bool> {
+    tokio::signal::ctrl_c().await?;
+    Ok(true)
   }
 
-  async fn render_loop(mut self, ws: &Workspace, should_exit: &Arc<Notify>) -> Result<bool> {
+  /// Renders `ws` until `should_exit` fires, always drawing the most recently loaded workspace.
+  async fn render_loop(
+    mut self,
+    ws: &watch::Receiver<Workspace>,
+    should_exit: &Arc<Notify>,
+  ) -> Result<bool> {
     let exit_early = {
       let this = &self;
 
-      let input_future = this.handle_input();
+      let input_future = this.handle_input(ws);
       tokio::pin!(input_future);
 
       let draw_future = async move {
         loop {
-          this.render(ws).unwrap();
+          let ws = ws.borrow().clone();
+          this.render(&ws).unwrap();
           tokio::time::sleep(TICK_RATE).await;
         }
       };
@@ -232,7 +736,8 @@ pub trait Renderer: Sized + Send + Sync {
       }
     };
 
-    self.complete(ws)?;
+    let ws = ws.borrow().clone();
+    self.complete(&ws)?;
 
     Ok(exit_early)
   }
@@ -243,9 +748,14 @@ pub struct InlineRenderer {
   diff: Mutex<ansi_diff::Diff>,
 }
 
+impl Default for InlineRenderer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
 impl InlineRenderer {
   pub fn new() -> Self {
-    // TODO: do we need a different rendering strategy if there's no tty?
     let (w, h) = match crossterm::terminal::size() {
       Ok((w, h)) if w > 0 && h > 0 => (w, h),
       _ => (80, 40),
@@ -254,6 +764,15 @@ impl InlineRenderer {
     InlineRenderer { diff }
   }
 
+  fn status_label(process: &Process) -> &'static str {
+    match process.status() {
+      ProcessStatus::Running => "running...",
+      ProcessStatus::Succeeded => "finished",
+      ProcessStatus::Failed => "failed",
+      ProcessStatus::Crashed => "crashed, restarting...",
+    }
+  }
+
   fn build_output(ws: &Workspace) -> Result<String> {
     let mut output = Vec::new();
 
@@ -277,11 +796,7 @@ impl InlineRenderer {
           // TODO: distinguish stdout from stderr
           writeln!(&mut output, "{}", line.line)?;
         }
-        let status = if process.finished() {
-          "finished"
-        } else {
-          "running..."
-        };
+        let status = Self::status_label(process);
 
         meta!("└─ {status}\n");
       }
@@ -320,11 +835,7 @@ impl InlineRenderer {
           // TODO: distinguish stdout from stderr
           writeln!(&mut output, "{}", line.line)?;
         }
-        let status = if process.finished() {
-          "finished"
-        } else {
-          "running..."
-        };
+        let status = Self::status_label(process);
 
         meta!("{monorepo_prefix}└─ {status}\n");
       }
@@ -346,3 +857,150 @@ impl Renderer for InlineRenderer {
     self.render(ws)
   }
 }
+
+struct StreamState {
+  printed: usize,
+  status: ProcessStatus,
+}
+
+type StreamKey = (Option<PackageIndex>, usize);
+
+/// Streams each line of process output once, for non-interactive environments like CI.
+pub struct PlainRenderer {
+  streams: Mutex<HashMap<StreamKey, StreamState>>,
+  color: bool,
+}
+
+impl Default for PlainRenderer {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl PlainRenderer {
+  pub fn new() -> Self {
+    PlainRenderer {
+      streams: Mutex::default(),
+      color: env::var_os("FORCE_COLOR").is_some(),
+    }
+  }
+
+  fn marker(&self, output: &mut Vec<u8>, label: &str, color: Color, msg: &str) -> Result<()> {
+    if self.color {
+      execute!(output, SetForegroundColor(color))?;
+    }
+    write!(output, "[{label}] {msg}")?;
+    if self.color {
+      execute!(output, ResetColor)?;
+    }
+    writeln!(output)?;
+    Ok(())
+  }
+
+  fn stream_process(
+    &self,
+    output: &mut Vec<u8>,
+    key: StreamKey,
+    label: &str,
+    process: &Process,
+  ) -> Result<()> {
+    let mut streams = self.streams.lock().unwrap();
+    let state = match streams.entry(key) {
+      Entry::Occupied(entry) => entry.into_mut(),
+      Entry::Vacant(entry) => {
+        self.marker(output, label, Color::Magenta, "started")?;
+        entry.insert(StreamState {
+          printed: 0,
+          status: ProcessStatus::Running,
+        })
+      }
+    };
+
+    // Read the status before the logs so that lines emitted right before exit aren't lost.
+    let status = process.status();
+
+    let stdout = process.stdout();
+    for line in stdout.iter_since(state.printed) {
+      let line = if self.color {
+        Cow::Borrowed(line.line.as_str())
+      } else {
+        strip_ansi(&line.line)
+      };
+      writeln!(output, "[{label}] {line}")?;
+    }
+    state.printed = stdout.total_pushed();
+    drop(stdout);
+
+    if status != state.status {
+      let secs = process.duration().as_secs_f64();
+      let (color, msg) = match status {
+        ProcessStatus::Running => (Color::Magenta, "restarted".to_string()),
+        ProcessStatus::Succeeded => (Color::Green, format!("finished in {secs:.1}s")),
+        ProcessStatus::Failed => (Color::Red, format!("failed after {secs:.1}s")),
+        ProcessStatus::Crashed => (Color::Red, format!("crashed after {secs:.1}s")),
+      };
+      self.marker(output, label, color, &msg)?;
+      state.status = status;
+    }
+
+    Ok(())
+  }
+}
+
+impl Renderer for PlainRenderer {
+  fn render(&self, ws: &Workspace) -> Result<()> {
+    let mut output = Vec::new();
+
+    for (i, process) in ws.processes().iter().enumerate() {
+      let label = format!("ws/{}", process.script());
+      self.stream_process(&mut output, (None, i), &label, process)?;
+    }
+
+    for pkg in ws.package_display_order() {
+      for (i, process) in pkg.processes().iter().enumerate() {
+        let label = format!("{}/{}", pkg.name, process.script());
+        self.stream_process(&mut output, (Some(pkg.index), i), &label, process)?;
+      }
+    }
+
+    if !output.is_empty() {
+      let mut stdout = std::io::stdout();
+      stdout.write_all(&output)?;
+      stdout.flush()?;
+    }
+
+    Ok(())
+  }
+
+  fn complete(self, ws: &Workspace) -> Result<()> {
+    self.render(ws)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_grid_dims() {
+    assert_eq!(grid_dims(1, 4, 4), (1, 1));
+    assert_eq!(grid_dims(3, 4, 4), (2, 2));
+    assert_eq!(grid_dims(4, 4, 4), (2, 2));
+    assert_eq!(grid_dims(5, 4, 4), (3, 2));
+    assert_eq!(grid_dims(5, 4, 1), (4, 1));
+    assert_eq!(grid_dims(6, 1, 3), (1, 3));
+    assert_eq!(grid_dims(0, 2, 2), (1, 1));
+  }
+
+  #[test]
+  fn test_strip_ansi() {
+    assert_eq!(strip_ansi("plain text"), "plain text");
+    assert_eq!(strip_ansi("\u{1b}[31merror\u{1b}[0m: bad"), "error: bad");
+    assert_eq!(strip_ansi("\u{1b}[1;32mok\u{1b}[39;22m"), "ok");
+    assert_eq!(
+      strip_ansi("\u{1b}]8;;https://example.com\u{7}link\u{1b}]8;;\u{1b}\\"),
+      "link"
+    );
+    assert_eq!(strip_ansi("\u{1b}[2K\u{1b}[1Gdone"), "done");
+  }
+}
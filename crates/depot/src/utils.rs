@@ -42,6 +42,47 @@ pub fn remove_dir_all_if_exists(dir: impl AsRef<Path>) -> Result<()> {
   fs::remove_dir_all(dir).with_context(|| format!("Could not remove dir: {}", dir.display()))
 }
 
+/// Converts JSON with comments and trailing commas, like a tsconfig, into plain JSON.
+pub fn jsonc_to_json(contents: &str) -> String {
+  let mut json = String::with_capacity(contents.len());
+  let mut chars = contents.chars().peekable();
+  while let Some(c) = chars.next() {
+    match c {
+      '"' => {
+        json.push(c);
+        while let Some(c) = chars.next() {
+          json.push(c);
+          match c {
+            '\\' => json.extend(chars.next()),
+            '"' => break,
+            _ => {}
+          }
+        }
+      }
+      '/' if chars.peek() == Some(&'/') => while chars.next_if(|c| *c != '\n').is_some() {},
+      '/' if chars.peek() == Some(&'*') => {
+        chars.next();
+        let mut prev = None;
+        for c in chars.by_ref() {
+          if prev == Some('*') && c == '/' {
+            break;
+          }
+          prev = Some(c);
+        }
+      }
+      '}' | ']' => {
+        let trimmed = json.trim_end().len();
+        if json[..trimmed].ends_with(',') {
+          json.truncate(trimmed - 1);
+        }
+        json.push(c);
+      }
+      c => json.push(c),
+    }
+  }
+  json
+}
+
 #[macro_export]
 macro_rules! test_packages {
   ($($manifest:tt),*) => {{
@@ -101,13 +142,34 @@ macro_rules! shareable {
   };
 }
 
+/// Finds an executable installed by the dependencies in any of `roots`, in order.
+pub fn find_bin(roots: &[&Path], name: &str) -> Option<PathBuf> {
+  roots
+    .iter()
+    .map(|root| root.join("node_modules").join(".bin").join(name))
+    .find(|path| path.exists())
+}
+
 pub fn find_node() -> Option<PathBuf> {
   pathsearch::find_executable_in_path("node")
 }
 
-pub fn find_pnpm(root: Option<&Path>) -> Option<PathBuf> {
-  let pnpm_in_root = root
-    .map(|root| root.join("bin").join("pnpm"))
-    .filter(|root| root.exists());
-  pnpm_in_root.or_else(|| pathsearch::find_executable_in_path("pnpm"))
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_jsonc_to_json() {
+    let jsonc = r#"{
+  // A comment with "quotes"
+  "a": "http://x/*y*/", /* block
+  comment */ "b": [1, 2,],
+  "c": "\\",
+}"#;
+    let value: serde_json::Value = serde_json::from_str(&jsonc_to_json(jsonc)).unwrap();
+    assert_eq!(
+      value,
+      serde_json::json!({"a": "http://x/*y*/", "b": [1, 2], "c": "\\"})
+    );
+  }
 }
@@ -114,8 +114,7 @@ mod test {
       vec![0, 1],
       |_| panic!(),
       |i| match i {
-        0 => vec![2],
-        1 => vec![2],
+        0 | 1 => vec![2],
         2 => vec![3],
         3 => vec![],
         _ => unreachable!(),
@@ -148,20 +147,16 @@ mod test {
     assert_eq!(
       dg.roots().copied().collect::<HashSet<_>>(),
       hashset! { 0, 1 }
-    )
+    );
   }
 
   #[test]
   fn dep_graph_cycle() {
-    let dg = DepGraph::build(
-      vec![0],
-      |i| i.to_string(),
-      |i| match i {
-        0 => vec![1],
-        1 => vec![0],
-        _ => unreachable!(),
-      },
-    );
+    let dg = DepGraph::build(vec![0], ToString::to_string, |i| match i {
+      0 => vec![1],
+      1 => vec![0],
+      _ => unreachable!(),
+    });
     assert!(dg.is_err());
   }
 }
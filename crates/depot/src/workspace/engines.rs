@@ -0,0 +1,174 @@
+//! Checks that the installed versions of Node and the package manager satisfy the
+//! workspace's `engines` and `packageManager` fields, or else Depot's own minimums.
+
+use std::{
+  cell::OnceCell,
+  path::{Path, PathBuf},
+};
+
+use anyhow::{Result, bail};
+use package_json_schema::PackageJson;
+use semver::Version;
+
+use super::{
+  Workspace,
+  package_manager::PackageManager,
+  version_range::{self, VersionRange},
+};
+
+/// The oldest versions of Node and pnpm that Depot supports.
+pub const MIN_NODE_VERSION: u64 = 24;
+pub const MIN_PNPM_VERSION: u64 = 10;
+
+/// A range of versions that a tool must satisfy.
+pub struct Requirement {
+  pub range: VersionRange,
+
+  /// What requires the range, like `engines.node` or Depot itself.
+  pub source: String,
+}
+
+impl Requirement {
+  fn new(range: &str, source: impl Into<String>) -> Result<Self> {
+    let source = source.into();
+    let range = range
+      .parse::<VersionRange>()
+      .map_err(|e| e.context(format!("Invalid `{source}`")))?;
+    Ok(Requirement { range, source })
+  }
+}
+
+/// Runs `program --version` and parses its output.
+pub fn tool_version(program: &Path) -> Result<Version> {
+  cfg_if::cfg_if! {
+    if #[cfg(windows)] {
+      let mut cmd = std::process::Command::new("sh");
+      cmd.arg(program);
+    } else {
+      let mut cmd = std::process::Command::new(program);
+    }
+  }
+  let output = cmd.arg("--version").output()?;
+  version_range::parse_version(&String::from_utf8(output.stdout)?)
+}
+
+/// The version of an installed tool, which is only run the first time it is needed.
+pub struct ToolVersion {
+  program: PathBuf,
+  version: OnceCell<Version>,
+}
+
+impl ToolVersion {
+  pub fn new(program: PathBuf) -> Self {
+    ToolVersion {
+      program,
+      version: OnceCell::new(),
+    }
+  }
+
+  pub fn get(&self) -> Result<&Version> {
+    if let Some(version) = self.version.get() {
+      return Ok(version);
+    }
+    let version = tool_version(&self.program)?;
+    Ok(self.version.get_or_init(|| version))
+  }
+}
+
+/// The version of the package manager pinned by the `packageManager` field, if any.
+pub fn pinned_version(manifest: &PackageJson, pm: PackageManager) -> Option<&str> {
+  let field = manifest.package_manager.as_deref()?;
+  let (_, version) = field.split_once('@')?;
+  let version = version.split('+').next().unwrap();
+  (PackageManager::from_field(field) == Some(pm)).then_some(version)
+}
+
+/// The required Node version from `engines.node`, or else Depot's minimum.
+pub fn node_requirement(manifest: Option<&PackageJson>) -> Result<Requirement> {
+  let engine = manifest
+    .and_then(|manifest| manifest.engines.as_ref())
+    .and_then(|engines| engines.get("node"));
+  match engine {
+    Some(range) => Requirement::new(range, "engines.node"),
+    None => Requirement::new(&format!(">={MIN_NODE_VERSION}"), "Depot"),
+  }
+}
+
+/// The required package manager version from `engines`, then `packageManager`, or else
+/// Depot's minimum for pnpm. A pinned `packageManager` version allows compatible versions.
+pub fn package_manager_requirement(
+  manifest: Option<&PackageJson>,
+  pm: PackageManager,
+) -> Result<Option<Requirement>> {
+  let engine = manifest
+    .and_then(|manifest| manifest.engines.as_ref())
+    .and_then(|engines| engines.get(pm.name()));
+  if let Some(range) = engine {
+    return Ok(Some(Requirement::new(range, format!("engines.{pm}"))?));
+  }
+  if let Some(version) = manifest.and_then(|manifest| pinned_version(manifest, pm)) {
+    return Ok(Some(Requirement::new(
+      &format!("^{version}"),
+      "packageManager",
+    )?));
+  }
+  match pm {
+    PackageManager::Pnpm => Ok(Some(Requirement::new(
+      &format!(">={MIN_PNPM_VERSION}"),
+      "Depot",
+    )?)),
+    _ => Ok(None),
+  }
+}
+
+fn check_tool(name: &str, version: &Version, requirement: &Requirement) -> Result<()> {
+  if !requirement.range.matches(version) {
+    bail!(
+      "Found {name} {version}, but {} requires {name} {}. Install a matching version, or pass --ignore-engines to skip this check.",
+      requirement.source,
+      requirement.range
+    );
+  }
+  Ok(())
+}
+
+/// Fails if the installed Node or package manager, whose version is `pm_version`, doesn't
+/// satisfy the workspace.
+pub fn check(ws: &Workspace, pm_version: &ToolVersion) -> Result<()> {
+  let manifest = Some(&ws.manifest.manifest);
+  if let Some(node) = crate::utils::find_node() {
+    check_tool("node", &tool_version(&node)?, &node_requirement(manifest)?)?;
+  }
+
+  let pm = ws.package_manager;
+  if let Some(requirement) = package_manager_requirement(manifest, pm)? {
+    check_tool(pm.name(), pm_version.get()?, &requirement)?;
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_requirements() -> Result<()> {
+    let manifest = PackageJson::try_from(
+      r#"{"packageManager": "pnpm@10.4.1+sha512.abc", "engines": {"node": ">=22"}}"#.to_string(),
+    )?;
+    let version = |v: &str| version_range::parse_version(v).unwrap();
+
+    let node = node_requirement(Some(&manifest))?;
+    assert_eq!(node.source, "engines.node");
+    assert!(node.range.matches(&version("22.0.0")));
+    assert!(!node_requirement(None)?.range.matches(&version("22.0.0")));
+
+    let pnpm = package_manager_requirement(Some(&manifest), PackageManager::Pnpm)?.unwrap();
+    assert_eq!(pnpm.source, "packageManager");
+    assert!(pnpm.range.matches(&version("10.5.0")));
+    assert!(!pnpm.range.matches(&version("11.0.0")));
+
+    assert!(package_manager_requirement(Some(&manifest), PackageManager::Npm)?.is_none());
+    Ok(())
+  }
+}
@@ -0,0 +1,91 @@
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+use std::{env, fmt, path::Path};
+
+/// Environment variables that Depot passes to the processes it spawns.
+///
+/// Values often hold secrets, so the [`Debug`] output only shows names.
+#[derive(Default, Clone)]
+pub struct EnvVars(IndexMap<String, String>);
+
+impl EnvVars {
+  /// Adds variables from a config map, overriding existing ones.
+  pub fn extend(&mut self, vars: &IndexMap<String, String>) {
+    self
+      .0
+      .extend(vars.iter().map(|(key, value)| (key.clone(), value.clone())));
+  }
+
+  /// Adds variables from `.env`, `.env.<profile>`, and `.env.local` in `dir`, with later
+  /// files taking precedence.
+  pub fn load_files(&mut self, dir: &Path, profile: &str) -> Result<()> {
+    for file_name in [
+      ".env".to_string(),
+      format!(".env.{profile}"),
+      ".env.local".to_string(),
+    ] {
+      let path = dir.join(file_name);
+      if !path.exists() {
+        continue;
+      }
+      let vars = dotenvy::from_path_iter(&path)
+        .with_context(|| format!("Failed to read env file: {}", path.display()))?;
+      for var in vars {
+        let (key, value) =
+          var.with_context(|| format!("Failed to parse env file: {}", path.display()))?;
+        self.0.insert(key, value);
+      }
+    }
+    Ok(())
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.0.is_empty()
+  }
+
+  /// Sets the variables on `cmd`, except those already set in Depot's own environment.
+  pub fn apply(&self, cmd: &mut tokio::process::Command) {
+    for (key, value) in &self.0 {
+      if env::var_os(key).is_none() {
+        cmd.env(key, value);
+      }
+    }
+  }
+}
+
+impl fmt::Debug for EnvVars {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_map()
+      .entries(self.0.keys().map(|key| (key, "<redacted>")))
+      .finish()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use indexmap::indexmap;
+
+  #[test]
+  fn test_env_precedence() -> Result<()> {
+    let tmp = tempfile::tempdir()?;
+    let dir = tmp.path();
+    std::fs::write(dir.join(".env"), "A=env\nB=env\nC=env\n")?;
+    std::fs::write(dir.join(".env.staging"), "B=staging\nC=staging\n")?;
+    std::fs::write(dir.join(".env.local"), "C=local\n")?;
+
+    let mut vars = EnvVars::default();
+    vars.extend(&indexmap! { "A".into() => "config".into(), "D".into() => "config".into() });
+    vars.load_files(dir, "staging")?;
+
+    assert_eq!(vars.0["A"], "env");
+    assert_eq!(vars.0["B"], "staging");
+    assert_eq!(vars.0["C"], "local");
+    assert_eq!(vars.0["D"], "config");
+    assert_eq!(
+      format!("{vars:?}"),
+      r#"{"A": "<redacted>", "D": "<redacted>", "B": "<redacted>", "C": "<redacted>"}"#
+    );
+    Ok(())
+  }
+}
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 use crate::utils;
 
 /// Data structure for tracking when Depot commands were last executed.
-#[derive(Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Default)]
 pub struct Fingerprints {
   fingerprints: HashMap<String, SystemTime>,
 }
@@ -49,7 +49,15 @@ impl Fingerprints {
     self.fingerprints.insert(key, SystemTime::now());
   }
 
-  fn file_path(root: &Path) -> PathBuf {
+  /// The recorded timestamps, keyed like [`Fingerprints::can_skip`].
+  pub fn times(&self) -> impl Iterator<Item = (&str, SystemTime)> {
+    self
+      .fingerprints
+      .iter()
+      .map(|(key, time)| (key.as_str(), *time))
+  }
+
+  pub fn file_path(root: &Path) -> PathBuf {
     root.join("node_modules").join(".depot-fingerprints.json")
   }
 
@@ -0,0 +1,67 @@
+//! The tools that Depot can use to lint and format source files.
+
+/// A program and its arguments, to be followed by the paths of source files.
+pub type Invocation = (&'static str, &'static [&'static str]);
+
+#[derive(
+  Copy, Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Linter {
+  /// `biome` for both linting and formatting.
+  #[default]
+  Biome,
+
+  /// `eslint` for linting and `prettier` for formatting.
+  Eslint,
+}
+
+impl Linter {
+  /// Reports lint and formatting issues without changing files.
+  pub fn check(self) -> &'static [Invocation] {
+    match self {
+      Linter::Biome => &[("biome", &["check", "--colors=force"])],
+      Linter::Eslint => &[("eslint", &["--color"]), ("prettier", &["--check"])],
+    }
+  }
+
+  /// Fixes lint issues where possible and formats files, in order.
+  pub fn fix(self) -> &'static [Invocation] {
+    match self {
+      Linter::Biome => &[("biome", &["check", "--fix"])],
+      Linter::Eslint => &[("eslint", &["--fix"]), ("prettier", &["--write"])],
+    }
+  }
+
+  /// Formats files, or with `check`, fails if they aren't formatted.
+  pub fn format(self, check: bool) -> Invocation {
+    match (self, check) {
+      (Linter::Biome, false) => ("biome", &["format", "--write"]),
+      (Linter::Biome, true) => ("biome", &["format"]),
+      (Linter::Eslint, false) => ("prettier", &["--write"]),
+      (Linter::Eslint, true) => ("prettier", &["--check"]),
+    }
+  }
+
+  /// Names of the config files read by the tools, any of which is sufficient.
+  pub fn config_files(self) -> &'static [&'static str] {
+    match self {
+      Linter::Biome => &["biome.json", "biome.jsonc"],
+      Linter::Eslint => &["eslint.config.mjs", "eslint.config.js", "eslint.config.cjs"],
+    }
+  }
+
+  /// Packages that provide the tools, installed at the workspace root.
+  pub fn dev_dependencies(self) -> &'static [&'static str] {
+    match self {
+      Linter::Biome => &["@biomejs/biome"],
+      Linter::Eslint => &[
+        "eslint",
+        "@eslint/js",
+        "typescript-eslint",
+        "prettier",
+        "eslint-config-prettier",
+      ],
+    }
+  }
+}
@@ -2,7 +2,7 @@ use anyhow::{Context, Result};
 use std::{fs, path::Path};
 
 use package_json_schema::PackageJson;
-use serde::de::DeserializeOwned;
+use serde::{Serialize, de::DeserializeOwned};
 
 pub struct DepotManifest<Config> {
   pub manifest: PackageJson,
@@ -25,3 +25,10 @@ impl<Config: DeserializeOwned> DepotManifest<Config> {
     Ok(DepotManifest { manifest, config })
   }
 }
+
+impl<Config: Serialize> DepotManifest<Config> {
+  /// The manifest including its config, for comparing manifests across reloads.
+  pub fn to_value(&self) -> serde_json::Value {
+    serde_json::json!([self.manifest, self.config])
+  }
+}
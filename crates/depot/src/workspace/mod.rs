@@ -1,39 +1,83 @@
 use self::{
   dep_graph::DepGraph,
+  env_vars::EnvVars,
   fingerprint::Fingerprints,
-  package::{PackageGraph, PackageIndex},
+  linter::Linter,
+  package::{PackageGraph, PackageIndex, PackageName},
+  package_manager::PackageManager,
   process::Process,
+  profile::{DEFAULT_PROFILE, Profile, ProfileConfig},
+};
+use crate::{
+  CommonArgs,
+  commands::custom::{self, CustomCommandConfig},
+  shareable, utils,
 };
-use crate::{CommonArgs, shareable, utils};
 
-use anyhow::{Context, Result, anyhow};
+use anyhow::{Context, Result};
 use futures::{
   StreamExt,
   stream::{self, TryStreamExt},
 };
+use indexmap::IndexMap;
 use log::{debug, warn};
 use manifest::DepotManifest;
 use package::Package;
 use std::{
+  cell::RefCell,
   cmp::Ordering,
+  collections::HashSet,
   env,
   fmt::{self, Debug},
-  iter,
+  fs, iter,
   path::{Path, PathBuf},
   sync::{Arc, RwLock, RwLockReadGuard},
 };
 
 mod dep_graph;
-mod fingerprint;
+pub mod engines;
+pub mod env_vars;
+pub mod fingerprint;
+pub mod linter;
 mod manifest;
 pub mod package;
+pub mod package_manager;
 pub mod process;
+pub mod profile;
+mod reload;
 mod runner;
+pub mod version_range;
+
+pub use runner::{RunReport, Task, TaskReport, TaskStatus};
 
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, Default)]
 #[serde(rename_all = "kebab-case")]
 pub struct WorkspaceDepotConfig {
   pub depot_version: String,
+
+  /// How many times in a row a crashed process is restarted in watch mode before failing.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub max_restarts: Option<u32>,
+
+  /// Build profiles selectable with `--profile`, in addition to `dev` and `release`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub profiles: Option<IndexMap<String, ProfileConfig>>,
+
+  /// Commands run with `depot <name>`, in addition to the built-in ones.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub commands: Option<IndexMap<String, CustomCommandConfig>>,
+
+  /// Dependencies of commands that replace their defaults, like `{"test": ["^build"]}`.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub deps: Option<IndexMap<String, Vec<String>>>,
+
+  /// The package manager to use instead of the detected one.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub package_manager: Option<PackageManager>,
+
+  /// The linter and formatter for packages that don't choose their own.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub linter: Option<Linter>,
 }
 
 pub type WorkspaceManifest = DepotManifest<WorkspaceDepotConfig>;
@@ -46,6 +90,9 @@ pub struct WorkspaceInner {
   /// The root directory of the workspace containing `package.json`.
   pub root: PathBuf,
 
+  /// The manifest at the workspace root.
+  pub manifest: WorkspaceManifest,
+
   /// All the packages in the workspace.
   pub packages: Vec<Package>,
 
@@ -58,9 +105,16 @@ pub struct WorkspaceInner {
   /// CLI arguments that apply to the whole workspace.
   pub common: CommonArgs,
 
+  /// The build profile selected with `--profile`.
+  pub profile: Profile,
+
+  /// The package manager used to install dependencies and run tools.
+  pub package_manager: PackageManager,
+
   roots: Vec<Package>,
   package_display_order: Vec<PackageIndex>,
   processes: RwLock<Vec<Arc<Process>>>,
+  tasks: RwLock<Vec<Task>>,
   fingerprints: RwLock<Fingerprints>,
 }
 
@@ -107,7 +161,7 @@ impl CommandInner {
   pub fn deps(&self) -> Vec<Command> {
     match self {
       CommandInner::Package(cmd) => cmd.deps(),
-      CommandInner::Workspace(_) => Vec::new(),
+      CommandInner::Workspace(cmd) => cmd.deps(),
     }
   }
 }
@@ -182,6 +236,11 @@ pub trait PackageCommand: CoreCommand + Debug + Send + Sync + 'static {
   fn runtime(&self) -> CommandRuntime {
     CommandRuntime::RunImmediately
   }
+
+  /// Files whose changes cause the command to rerun with `--incremental`.
+  fn input_files(&self, package: &Package) -> Vec<PathBuf> {
+    package.all_files().collect()
+  }
 }
 
 #[async_trait::async_trait]
@@ -192,6 +251,10 @@ pub trait WorkspaceCommand: CoreCommand + Debug + Send + Sync + 'static {
     self.name()
   }
 
+  fn deps(&self) -> Vec<Command> {
+    Vec::new()
+  }
+
   fn input_files(&self, _ws: &Workspace) -> Option<Vec<PathBuf>> {
     None
   }
@@ -199,16 +262,43 @@ pub trait WorkspaceCommand: CoreCommand + Debug + Send + Sync + 'static {
 
 pub const DEPOT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+const DEFAULT_MAX_RESTARTS: u32 = 5;
+
 impl Workspace {
+  /// Finds the nearest directory containing `cwd` with a `package.json`, without leaving
+  /// the git repository.
+  pub fn find_root(cwd: &Path) -> Result<PathBuf> {
+    let fs_root = cwd.ancestors().last().unwrap().to_path_buf();
+    let git_root = utils::get_git_root(cwd);
+    let max_ancestor: &Path = git_root.as_deref().unwrap_or(&fs_root);
+    find_workspace_root(max_ancestor, cwd)
+  }
+
+  /// The root of each package in the workspace at `root`: the directories in `packages/`
+  /// for a monorepo, or else `root` itself.
+  pub fn package_roots(root: &Path) -> Result<Vec<PathBuf>> {
+    let pkg_dir = root.join("packages");
+    if !pkg_dir.exists() {
+      return Ok(vec![root.to_path_buf()]);
+    }
+
+    let mut roots = Vec::new();
+    for entry in pkg_dir.read_dir()? {
+      let path = entry?.path();
+      if path.is_dir() {
+        roots.push(path);
+      }
+    }
+    roots.sort();
+    Ok(roots)
+  }
+
   pub async fn load(cwd: Option<PathBuf>, common: CommonArgs) -> Result<Self> {
     let cwd = match cwd {
       Some(cwd) => cwd,
       None => env::current_dir()?,
     };
-    let fs_root = cwd.ancestors().last().unwrap().to_path_buf();
-    let git_root = utils::get_git_root(&cwd);
-    let max_ancestor: &Path = git_root.as_deref().unwrap_or(&fs_root);
-    let root = find_workspace_root(max_ancestor, &cwd)?;
+    let root = Self::find_root(&cwd)?;
     debug!("Workspace root: `{}`", root.display());
 
     let pkg_dir = root.join("packages");
@@ -225,14 +315,21 @@ Double-check that this workspace is compatible and update depot.depot_version in
       );
     }
 
-    let pkg_roots = if monorepo {
-      pkg_dir
-        .read_dir()?
-        .map(|entry| Ok(entry?.path()))
-        .collect::<Result<Vec<_>>>()?
-    } else {
-      vec![root.clone()]
-    };
+    let profile = Profile::resolve(
+      common.profile.as_deref().unwrap_or(DEFAULT_PROFILE),
+      manifest
+        .config
+        .profiles
+        .as_ref()
+        .unwrap_or(&IndexMap::new()),
+    )?;
+    debug!("Build profile: {profile:?}");
+
+    custom::validate(&manifest.config)?;
+
+    let package_manager = PackageManager::resolve(&root, &manifest)?;
+
+    let pkg_roots = Self::package_roots(&root)?;
 
     let packages: Vec<_> = stream::iter(pkg_roots)
       .enumerate()
@@ -283,13 +380,17 @@ Double-check that this workspace is compatible and update depot.depot_version in
 
     let ws = Workspace::new(WorkspaceInner {
       root,
+      manifest,
       packages,
       package_display_order,
       monorepo,
       pkg_graph,
       common,
+      profile,
+      package_manager,
       roots,
       processes: RwLock::default(),
+      tasks: RwLock::default(),
       fingerprints,
     });
 
@@ -309,39 +410,87 @@ impl WorkspaceInner {
       .map(|idx| &self.packages[*idx])
   }
 
-  pub fn start_process(
+  /// Builds the command that runs `script` from the workspace's dependencies.
+  pub fn command(
     &self,
-    script: &'static str,
+    script: &str,
     configure: impl FnOnce(&mut tokio::process::Command),
-  ) -> Result<Arc<Process>> {
-    log::trace!("Starting process: {script}");
+  ) -> Result<tokio::process::Command> {
+    self.command_in(script, &[&self.root], configure)
+  }
 
-    let pnpm =
-      utils::find_pnpm(Some(&self.root)).ok_or(anyhow!("could not find pnpm on your system"))?;
+  /// Builds the command that runs `script`, looking for its binary in the dependencies
+  /// of `bin_roots` if the package manager can't run it.
+  pub fn command_in(
+    &self,
+    script: &str,
+    bin_roots: &[&Path],
+    configure: impl FnOnce(&mut tokio::process::Command),
+  ) -> Result<tokio::process::Command> {
+    let pm = self.package_manager;
+    let (program, exec_args) = match pm.exec_args() {
+      _ if script == pm.name() => (pm.find(Some(&self.root))?, None),
+      Some(exec_args) => (pm.find(Some(&self.root))?, Some(exec_args)),
+      None => {
+        let bin = utils::find_bin(bin_roots, script)
+          .or_else(|| pathsearch::find_executable_in_path(script))
+          .with_context(|| {
+            format!(
+              "Could not find `{script}` in node_modules or on your path. Try running `depot init`."
+            )
+          })?;
+        (bin, None)
+      }
+    };
 
     cfg_if::cfg_if! {
       if #[cfg(windows)] {
         let mut cmd = tokio::process::Command::new("sh");
-        cmd.arg(pnpm);
+        cmd.arg(program);
       } else {
-        let mut cmd = tokio::process::Command::new(pnpm);
+        let mut cmd = tokio::process::Command::new(program);
       }
     }
     cmd.current_dir(&self.root);
     cmd.env("NODE_PATH", self.root.join("node_modules"));
+    self.env()?.apply(&mut cmd);
+    cmd.env("DEPOT_PROFILE", &self.profile.name);
+    if !self.profile.define.is_empty() {
+      cmd.env("DEPOT_DEFINE", self.profile.define_json());
+    }
+    if let Some(node_env) = &self.profile.node_env {
+      cmd.env("NODE_ENV", node_env);
+    }
 
-    if script != "pnpm" {
-      cmd.args(["exec", script]);
+    if let Some(exec_args) = exec_args {
+      cmd.args(exec_args).arg(script);
     }
 
     configure(&mut cmd);
 
+    Ok(cmd)
+  }
+
+  /// Variables from the `.env` files at the workspace root.
+  pub fn env(&self) -> Result<EnvVars> {
+    let mut env = EnvVars::default();
+    env.load_files(&self.root, &self.profile.name)?;
+    Ok(env)
+  }
+
+  pub fn start_process(
+    &self,
+    script: &str,
+    configure: impl FnOnce(&mut tokio::process::Command),
+  ) -> Result<Arc<Process>> {
+    log::trace!("Starting process: {script}");
+    let cmd = self.command(script, configure)?;
     Ok(Arc::new(Process::new(script.to_owned(), cmd)?))
   }
 
   pub async fn exec(
     &self,
-    script: &'static str,
+    script: &str,
     configure: impl FnOnce(&mut tokio::process::Command),
   ) -> Result<()> {
     let process = self.start_process(script, configure)?;
@@ -353,15 +502,142 @@ impl WorkspaceInner {
     self.processes.read().unwrap()
   }
 
+  pub fn max_restarts(&self) -> u32 {
+    self
+      .manifest
+      .config
+      .max_restarts
+      .unwrap_or(DEFAULT_MAX_RESTARTS)
+  }
+
+  /// All tasks of the command currently being run.
+  pub fn tasks(&self) -> RwLockReadGuard<'_, Vec<Task>> {
+    self.tasks.read().unwrap()
+  }
+
   pub fn all_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
     self.packages.iter().flat_map(|pkg| pkg.all_files())
   }
+
+  /// Loads a fresh copy of this workspace from disk, e.g. after a manifest changed.
+  pub async fn reload(&self) -> Result<Workspace> {
+    self.fingerprints.read().unwrap().save(&self.root)?;
+    Workspace::load(Some(self.root.clone()), self.common.clone()).await
+  }
+
+  /// Kills every process started in this workspace, except in the packages in `keep`.
+  ///
+  /// [`Process::kill`] kills the whole process group, so the tools started by the package
+  /// manager don't outlive the reload and hold ports or write to `dist`.
+  fn kill_processes(&self, keep: &HashSet<PackageName>) {
+    let pkg_processes = self
+      .packages
+      .iter()
+      .filter(|pkg| !keep.contains(&pkg.name))
+      .map(|pkg| pkg.processes());
+    for processes in iter::once(self.processes()).chain(pkg_processes) {
+      for process in processes.iter() {
+        process.kill();
+      }
+    }
+  }
+
+  /// Summarizes the dependencies declared by every manifest and the pnpm workspace file,
+  /// so a reload can tell whether `init` needs to run again.
+  fn dependency_signature(&self) -> Vec<String> {
+    let workspace_file = fs::read_to_string(self.root.join("pnpm-workspace.yaml")).ok();
+    let manifests = iter::once((&self.root, &self.manifest.manifest)).chain(
+      self
+        .packages
+        .iter()
+        .map(|pkg| (&pkg.root, &pkg.manifest.manifest)),
+    );
+    manifests
+      .map(|(root, manifest)| {
+        serde_json::json!([
+          root,
+          manifest.dependencies,
+          manifest.dev_dependencies,
+          manifest.peer_dependencies,
+          manifest.optional_dependencies,
+        ])
+        .to_string()
+      })
+      .chain(workspace_file)
+      .collect()
+  }
 }
 
-pub type CommandGraph = DepGraph<Command>;
+/// An edge in the [`CommandGraph`].
+pub struct CommandDep {
+  pub command: Command,
+
+  /// If true, a package only waits for this command to finish in the packages it
+  /// depends on, and not in itself.
+  pub upstream: bool,
+}
+
+impl CommandDep {
+  pub fn new(command: Command) -> Self {
+    CommandDep {
+      command,
+      upstream: false,
+    }
+  }
+}
 
-pub fn build_command_graph(root: &Command) -> CommandGraph {
-  DepGraph::build(vec![root.clone()], |_| unreachable!(), |cmd| cmd.deps()).unwrap()
+/// The commands needed to run a root command.
+pub struct CommandGraph {
+  graph: DepGraph<Command>,
+  upstream_edges: HashSet<(Command, Command)>,
+}
+
+impl CommandGraph {
+  pub fn roots(&self) -> impl Iterator<Item = &Command> {
+    self.graph.roots()
+  }
+
+  /// The immediate dependencies of `cmd`, each paired with [`CommandDep::upstream`].
+  pub fn immediate_deps_for<'a>(
+    &'a self,
+    cmd: &'a Command,
+  ) -> impl Iterator<Item = (&'a Command, bool)> + 'a {
+    self.graph.immediate_deps_for(cmd).map(move |dep| {
+      let upstream = self.upstream_edges.contains(&(cmd.clone(), dep.clone()));
+      (dep, upstream)
+    })
+  }
+}
+
+/// Builds the graph of commands needed to run `root`, where `deps_for` gives the
+/// dependencies of each command.
+pub fn build_command_graph(
+  root: &Command,
+  deps_for: impl Fn(&Command) -> Vec<CommandDep>,
+) -> CommandGraph {
+  let upstream_edges = RefCell::new(HashSet::new());
+  let graph = DepGraph::build(
+    vec![root.clone()],
+    |_| unreachable!(),
+    |cmd| {
+      deps_for(cmd)
+        .into_iter()
+        .map(|dep| {
+          if dep.upstream {
+            upstream_edges
+              .borrow_mut()
+              .insert((cmd.clone(), dep.command.clone()));
+          }
+          dep.command
+        })
+        .collect()
+    },
+  )
+  .unwrap();
+  CommandGraph {
+    graph,
+    upstream_edges: upstream_edges.into_inner(),
+  }
 }
 
 #[cfg(test)]
@@ -373,7 +649,9 @@ mod test {
   #[test]
   fn test_command_graph() {
     let root = TestCommand::new(TestArgs::default()).kind();
-    let _cmd_graph = build_command_graph(&root);
+    let _cmd_graph = build_command_graph(&root, |cmd| {
+      cmd.deps().into_iter().map(CommandDep::new).collect()
+    });
     // TODO: finish this test
   }
 }
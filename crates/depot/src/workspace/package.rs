@@ -1,6 +1,7 @@
 use anyhow::{Context, Error, Result, bail, ensure};
 
 use ignore::WalkBuilder;
+use indexmap::IndexMap;
 use maplit::hashset;
 use std::{
   collections::HashSet,
@@ -11,9 +12,14 @@ use std::{
   sync::{Arc, OnceLock, RwLock, RwLockReadGuard},
 };
 
-use crate::{shareable, workspace::process::Process};
+use crate::{
+  shareable, utils,
+  workspace::process::{LineFilter, Process},
+};
 
-use super::{Workspace, dep_graph::DepGraph, manifest::DepotManifest};
+use super::{
+  Workspace, dep_graph::DepGraph, env_vars::EnvVars, linter::Linter, manifest::DepotManifest,
+};
 
 #[derive(Copy, Clone, clap::ValueEnum, serde::Serialize, serde::Deserialize)]
 pub enum Platform {
@@ -58,6 +64,62 @@ impl Target {
   }
 }
 
+/// A module format emitted by a bundled library.
+#[derive(Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum BundleFormat {
+  #[serde(rename = "es")]
+  Es,
+  #[serde(rename = "cjs")]
+  Cjs,
+}
+
+/// How a bundled library emits type declarations.
+#[derive(Copy, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+pub enum DtsMode {
+  /// A single `.d.ts` file for the whole library.
+  #[default]
+  #[serde(rename = "rollup")]
+  Rollup,
+  /// One `.d.ts` file per module.
+  #[serde(rename = "files")]
+  Files,
+}
+
+/// The tool that bundles a script or site package.
+#[derive(
+  Copy, Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+pub enum Bundler {
+  #[default]
+  #[serde(rename = "vite")]
+  Vite,
+  #[serde(rename = "esbuild")]
+  Esbuild,
+}
+
+fn default_bundle_formats() -> Vec<BundleFormat> {
+  vec![BundleFormat::Es, BundleFormat::Cjs]
+}
+
+/// Builds a library with Vite library mode instead of `tsc`.
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct BundleConfig {
+  #[serde(default = "default_bundle_formats")]
+  pub formats: Vec<BundleFormat>,
+
+  #[serde(default)]
+  pub dts: DtsMode,
+}
+
+impl Default for BundleConfig {
+  fn default() -> Self {
+    BundleConfig {
+      formats: default_bundle_formats(),
+      dts: DtsMode::default(),
+    }
+  }
+}
+
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Ord, PartialOrd)]
 pub struct PackageName {
   pub name: String,
@@ -125,6 +187,21 @@ pub struct PackageDepotConfig {
 
   #[serde(skip_serializing_if = "Option::is_none")]
   pub source_extensions: Option<Vec<String>>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub bundle: Option<BundleConfig>,
+
+  /// Environment variables for every process run in this package.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub env: Option<IndexMap<String, String>>,
+
+  /// The linter and formatter, overriding the workspace's.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub linter: Option<Linter>,
+
+  /// The bundler for a script or site package, which is Vite by default.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub bundler: Option<Bundler>,
 }
 
 impl Default for PackageDepotConfig {
@@ -135,6 +212,10 @@ impl Default for PackageDepotConfig {
       no_server: None,
       asset_extensions: None,
       source_extensions: None,
+      bundle: None,
+      env: None,
+      linter: None,
+      bundler: None,
     }
   }
 }
@@ -156,6 +237,10 @@ pub struct PackageInner {
   // Internals
   ws: OnceLock<Workspace>,
   processes: RwLock<Vec<Arc<Process>>>,
+
+  /// The package that replaced this one when the workspace was reloaded, which takes
+  /// over the processes of this package's tasks that kept running.
+  successor: OnceLock<Package>,
 }
 
 shareable!(Package, PackageInner);
@@ -172,6 +257,25 @@ impl Package {
     self.processes.read().unwrap()
   }
 
+  fn add_process(&self, process: Arc<Process>) {
+    let mut processes = self.processes.write().unwrap();
+    match self.successor.get() {
+      Some(successor) => successor.add_process(process),
+      None => processes.push(process),
+    }
+  }
+
+  /// Moves this package's processes to `successor`, along with any that it starts later.
+  pub(super) fn hand_over(&self, successor: &Package) {
+    // The write lock keeps `add_process` from adding to this package in the meantime.
+    #[allow(clippy::readonly_write_lock)]
+    let processes = self.processes.write().unwrap();
+    for process in processes.iter() {
+      successor.add_process(Arc::clone(process));
+    }
+    let _ = self.successor.set(successor.clone());
+  }
+
   pub fn from_parts(
     root: PathBuf,
     manifest: PackageManifest,
@@ -195,6 +299,7 @@ impl Package {
       index,
       ws: OnceLock::default(),
       processes: RwLock::default(),
+      successor: OnceLock::default(),
     }))
   }
 
@@ -222,25 +327,82 @@ impl Package {
     let manifest_path = root.join("package.json");
     let manifest = PackageManifest::load(&manifest_path)?;
     let target = Self::infer_target(&root, &manifest)?;
-    Self::from_parts(root, manifest, index, target)
+    let pkg = Self::from_parts(root, manifest, index, target)?;
+    let can_choose_bundler = target.is_script() || (target.is_site() && !pkg.uses_vike());
+    ensure!(
+      can_choose_bundler || pkg.bundler() == Bundler::Vite,
+      "Only script packages and sites without Vike can set a \"bundler\": {}",
+      manifest_path.display()
+    );
+    Ok(pkg)
   }
 
-  pub fn start_process(
+  /// Builds the command that runs `script` in the package's directory.
+  pub fn command(
     &self,
-    script: &'static str,
+    script: &str,
     configure: impl FnOnce(&mut tokio::process::Command),
-  ) -> Result<Arc<Process>> {
-    let process = self.workspace().start_process(script, |cmd| {
+  ) -> Result<tokio::process::Command> {
+    let env = self.env()?;
+    if !env.is_empty() {
+      log::debug!("Environment for {}: {env:?}", self.name);
+    }
+    let ws = self.workspace();
+    ws.command_in(script, &[&self.root, &ws.root], |cmd| {
       cmd.current_dir(&self.root);
+      env.apply(cmd);
       configure(cmd);
-    })?;
-    self.processes.write().unwrap().push(process.clone());
+    })
+  }
+
+  /// Variables for processes in this package. From lowest to highest precedence, these
+  /// come from the `env` config, the workspace `.env` files, and the package `.env` files.
+  /// Variables already set in Depot's environment always win.
+  pub fn env(&self) -> Result<EnvVars> {
+    let mut env = EnvVars::default();
+    if let Some(vars) = &self.manifest.config.env {
+      env.extend(vars);
+    }
+    let ws = self.workspace();
+    env.load_files(&ws.root, &ws.profile.name)?;
+    if self.root != ws.root {
+      env.load_files(&self.root, &ws.profile.name)?;
+    }
+    Ok(env)
+  }
+
+  pub fn start_process(
+    &self,
+    script: &str,
+    configure: impl FnOnce(&mut tokio::process::Command),
+  ) -> Result<Arc<Process>> {
+    self.start_filtered_process(script, None, configure)
+  }
+
+  /// Like [`Package::start_process`], but passes stdout through `filter`.
+  pub fn start_filtered_process(
+    &self,
+    script: &str,
+    filter: Option<LineFilter>,
+    configure: impl FnOnce(&mut tokio::process::Command),
+  ) -> Result<Arc<Process>> {
+    log::trace!("Starting process: {script}");
+    let cmd = self.command(script, configure)?;
+    let process = Arc::new(Process::with_filter(script.to_owned(), cmd, filter)?);
+    self.add_process(process.clone());
     Ok(process)
   }
 
+  /// Adds a pane for logs of work that Depot does itself in this package.
+  pub fn log_process(&self, name: &str) -> Arc<Process> {
+    let process = Arc::new(Process::log_only(name.to_owned()));
+    self.add_process(process.clone());
+    process
+  }
+
   pub async fn exec(
     &self,
-    script: &'static str,
+    script: &str,
     configure: impl FnOnce(&mut tokio::process::Command),
   ) -> Result<()> {
     self
@@ -249,6 +411,42 @@ impl Package {
       .await
   }
 
+  /// Finds an executable installed by the dependencies of the package or the workspace.
+  pub fn find_bin(&self, name: &str) -> Option<PathBuf> {
+    utils::find_bin(&[&self.root, &self.workspace().root], name)
+  }
+
+  /// True if this is a library built with Vite library mode.
+  pub fn is_bundled(&self) -> bool {
+    self.target.is_lib() && self.manifest.config.bundle.is_some()
+  }
+
+  /// The entry point of a script package, like `src/main.ts`.
+  pub fn entry_point(&self) -> Option<PathBuf> {
+    Self::find_source_file(&self.root, "main")
+  }
+
+  pub fn bundler(&self) -> Bundler {
+    self.manifest.config.bundler.unwrap_or_default()
+  }
+
+  /// True if the package.json defines a script called `name`.
+  pub fn has_script(&self, name: &str) -> bool {
+    let scripts = self.manifest.manifest.scripts.as_ref();
+    scripts.is_some_and(|scripts| matches!(scripts.get(name), Some(Some(_))))
+  }
+
+  /// The linter and formatter from the package config, or else the workspace config.
+  pub fn linter(&self) -> Linter {
+    let ws_linter = self.workspace().manifest.config.linter;
+    self
+      .manifest
+      .config
+      .linter
+      .or(ws_linter)
+      .unwrap_or_default()
+  }
+
   pub fn uses_vike(&self) -> bool {
     self.root.join("src/+config.ts").exists()
   }
@@ -298,24 +496,31 @@ impl PackageInner {
       })
   }
 
-  pub fn asset_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+  /// True if `path` is a file under `src` that should be copied to `dist`.
+  pub fn is_asset(&self, path: &Path) -> bool {
+    let src_dir = self.root.join("src");
+    let Ok(rel_path) = path.strip_prefix(&src_dir) else {
+      return false;
+    };
+    if rel_path.starts_with("assets") {
+      return true;
+    }
+
     let mut asset_extensions: HashSet<&str> =
       hashset! { "scss", "css", "jpeg", "jpg", "png", "svg", "wasm" };
     if let Some(exts) = &self.manifest.config.asset_extensions {
       asset_extensions.extend(exts.iter().map(String::as_str));
     }
+    path
+      .extension()
+      .and_then(|ext| ext.to_str())
+      .is_some_and(|ext| asset_extensions.contains(ext))
+  }
 
+  pub fn asset_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
     self
       .iter_files("src", false)
-      .filter_map(move |path| {
-        let ext = path.extension()?;
-        asset_extensions
-          .contains(ext.to_str().unwrap())
-          .then_some(path)
-      })
-      .chain(self.iter_files("src/assets", false))
-      .collect::<HashSet<_>>() // dedup
-      .into_iter()
+      .filter(|path| self.is_asset(path))
   }
 
   pub fn source_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
@@ -401,13 +606,14 @@ mod test {
         scope: Some("foo".into())
       }
     );
-    assert_eq!("@foo/bar", format!("{}", name));
+    assert_eq!("@foo/bar", format!("{name}"));
 
     let s = "@what/is/this";
     assert!(PackageName::from_str(s).is_err());
   }
 
   #[test]
+  #[allow(clippy::mutable_key_type)]
   fn test_package_graph() {
     let pkgs = crate::test_packages! [
       {"name": "a", "dependencies": {"b": "0.1.0"}},
@@ -432,4 +638,17 @@ mod test {
     assert!(dg.is_dependent_on(a, c));
     assert!(!dg.is_dependent_on(b, a));
   }
+
+  #[test]
+  fn test_is_asset() {
+    let [pkg] = crate::test_packages! [
+      {"name": "a", "depot": {"platform": "browser", "asset-extensions": ["txt"]}}
+    ];
+    let src = pkg.root.join("src");
+    assert!(pkg.is_asset(&src.join("style.css")));
+    assert!(pkg.is_asset(&src.join("nested/notes.txt")));
+    assert!(pkg.is_asset(&src.join("assets/data.json")));
+    assert!(!pkg.is_asset(&src.join("index.ts")));
+    assert!(!pkg.is_asset(&pkg.root.join("style.css")));
+  }
 }
@@ -0,0 +1,298 @@
+//! The package managers that Depot can use to install dependencies and run tools.
+//!
+//! Only Yarn 1 is supported. Yarn 2 and later take different flags, and don't install
+//! `node_modules` by default.
+
+use std::{
+  fmt,
+  path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow, ensure};
+
+use semver::Version;
+
+use super::{WorkspaceManifest, engines::ToolVersion, version_range};
+
+#[derive(
+  Copy, Clone, PartialEq, Eq, Debug, Default, clap::ValueEnum, serde::Serialize, serde::Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageManager {
+  #[default]
+  Pnpm,
+  Npm,
+  Yarn,
+  Bun,
+}
+
+/// Lockfiles in order of precedence, for workspaces that contain more than one.
+const LOCKFILES: &[(&str, PackageManager)] = &[
+  ("pnpm-lock.yaml", PackageManager::Pnpm),
+  ("package-lock.json", PackageManager::Npm),
+  ("yarn.lock", PackageManager::Yarn),
+  ("bun.lock", PackageManager::Bun),
+  ("bun.lockb", PackageManager::Bun),
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum DependencyKind {
+  Dev,
+  Peer,
+}
+
+impl PackageManager {
+  pub fn name(self) -> &'static str {
+    match self {
+      PackageManager::Pnpm => "pnpm",
+      PackageManager::Npm => "npm",
+      PackageManager::Yarn => "yarn",
+      PackageManager::Bun => "bun",
+    }
+  }
+
+  pub fn install_url(self) -> &'static str {
+    match self {
+      PackageManager::Pnpm => "https://pnpm.io/installation",
+      PackageManager::Npm => "https://docs.npmjs.com/downloading-and-installing-node-js-and-npm",
+      PackageManager::Yarn => "https://yarnpkg.com/getting-started/install",
+      PackageManager::Bun => "https://bun.sh/docs/installation",
+    }
+  }
+
+  /// Parses the `packageManager` field of a manifest, like `pnpm@10.0.0`.
+  pub fn from_field(field: &str) -> Option<Self> {
+    let name = field.split_once('@').map_or(field, |(name, _)| name);
+    match name.trim() {
+      "pnpm" => Some(PackageManager::Pnpm),
+      "npm" => Some(PackageManager::Npm),
+      "yarn" => Some(PackageManager::Yarn),
+      "bun" => Some(PackageManager::Bun),
+      _ => None,
+    }
+  }
+
+  /// Detects the package manager of the workspace at `root` from its `packageManager`
+  /// field, then its lockfile, defaulting to pnpm.
+  pub fn detect(root: &Path, field: Option<&str>) -> Self {
+    field
+      .and_then(Self::from_field)
+      .or_else(|| {
+        LOCKFILES
+          .iter()
+          .find(|(file, _)| root.join(file).exists())
+          .map(|(_, pm)| *pm)
+      })
+      .unwrap_or_default()
+  }
+
+  /// The package manager set in the workspace config, or else the detected one. Fails if
+  /// the `packageManager` field pins an unsupported version.
+  pub fn resolve(root: &Path, manifest: &WorkspaceManifest) -> Result<Self> {
+    let field = manifest.manifest.package_manager.as_deref();
+    let pm = manifest
+      .config
+      .package_manager
+      .unwrap_or_else(|| Self::detect(root, field));
+    log::debug!("Package manager: {pm}");
+
+    let pinned = field
+      .filter(|field| Self::from_field(field) == Some(pm))
+      .and_then(|field| field.split_once('@'))
+      .and_then(|(_, version)| version_range::parse_version(version.split('+').next()?).ok());
+    if let Some(version) = pinned {
+      pm.ensure_supported(&version)?;
+    }
+    Ok(pm)
+  }
+
+  /// Fails if the installed package manager, whose version is `version`, is a version
+  /// that Depot doesn't support.
+  pub fn ensure_installed_supported(self, version: &ToolVersion) -> Result<()> {
+    if self == PackageManager::Yarn {
+      self.ensure_supported(version.get()?)?;
+    }
+    Ok(())
+  }
+
+  /// Fails if Depot doesn't support `version` of this package manager.
+  pub fn ensure_supported(self, version: &Version) -> Result<()> {
+    ensure!(
+      self != PackageManager::Yarn || version.major < 2,
+      "Depot only supports Yarn 1, but found Yarn {version}. Use Yarn 1, or another package manager."
+    );
+    Ok(())
+  }
+
+  /// The lockfile of this package manager in the workspace at `root`, if it exists.
+  pub fn find_lockfile(self, root: &Path) -> Option<PathBuf> {
+    LOCKFILES
+      .iter()
+      .filter(|(_, pm)| *pm == self)
+      .map(|(file, _)| root.join(file))
+      .find(|path| path.exists())
+  }
+
+  /// The file that an install writes to `node_modules`, which is newer than the lockfile
+  /// if the lockfile has been installed.
+  pub fn install_marker(self) -> &'static str {
+    match self {
+      PackageManager::Pnpm => ".modules.yaml",
+      PackageManager::Npm => ".package-lock.json",
+      PackageManager::Yarn => ".yarn-integrity",
+      // Bun writes no such file, so fall back to the directory itself.
+      PackageManager::Bun => ".",
+    }
+  }
+
+  /// Finds the package manager binary, preferring one in `<root>/bin`.
+  pub fn find(self, root: Option<&Path>) -> Result<PathBuf> {
+    let in_root = root
+      .map(|root| root.join("bin").join(self.name()))
+      .filter(|path| path.exists());
+    in_root
+      .or_else(|| pathsearch::find_executable_in_path(self.name()))
+      .ok_or_else(|| {
+        anyhow!(
+          "Failed to find `{self}` installed on your path. Depot requires {self} to be installed. See: {}",
+          self.install_url()
+        )
+      })
+  }
+
+  /// Arguments that run a binary from the workspace's dependencies, followed by its name,
+  /// or `None` if the binary must be run directly.
+  pub fn exec_args(self) -> Option<&'static [&'static str]> {
+    match self {
+      PackageManager::Pnpm | PackageManager::Yarn => Some(&["exec"]),
+      PackageManager::Npm => Some(&["exec", "--no", "--"]),
+      // `bun x` downloads and runs a binary from the registry if it isn't installed.
+      PackageManager::Bun => None,
+    }
+  }
+
+  /// Arguments that run the `scripts` entry `name` with `args`.
+  pub fn run_args(self, name: &str, args: &[String]) -> Vec<String> {
+    let mut run_args = vec!["run".to_string(), name.to_string()];
+    if self == PackageManager::Npm && !args.is_empty() {
+      run_args.push("--".into());
+    }
+    run_args.extend(args.iter().cloned());
+    run_args
+  }
+
+  /// Arguments that install the dependencies of the workspace.
+  pub fn install_args(self, offline: bool) -> Vec<&'static str> {
+    let mut args = vec!["install"];
+    if offline {
+      args.push(self.offline_flag());
+    }
+    args
+  }
+
+  /// Arguments that add dependencies of `kind`, followed by their names. With
+  /// `workspace_root`, they are added to the root of a monorepo.
+  pub fn add_args(self, kind: DependencyKind, workspace_root: bool) -> Vec<&'static str> {
+    let mut args = match (self, kind) {
+      (PackageManager::Npm, DependencyKind::Dev) => vec!["install", "--save-dev"],
+      (PackageManager::Npm, DependencyKind::Peer) => vec!["install", "--save-peer"],
+      (PackageManager::Pnpm, DependencyKind::Dev) => vec!["add", "--save-dev"],
+      (PackageManager::Pnpm, DependencyKind::Peer) => vec!["add", "--save-peer"],
+      (PackageManager::Yarn | PackageManager::Bun, DependencyKind::Dev) => vec!["add", "--dev"],
+      (PackageManager::Yarn | PackageManager::Bun, DependencyKind::Peer) => vec!["add", "--peer"],
+    };
+    if workspace_root {
+      // npm and Bun add to the root when run from it.
+      match self {
+        PackageManager::Pnpm => args.push("--workspace-root"),
+        PackageManager::Yarn => args.push("--ignore-workspace-root-check"),
+        PackageManager::Npm | PackageManager::Bun => {}
+      }
+    }
+    args
+  }
+
+  /// The flag to install only from the local cache.
+  pub fn offline_flag(self) -> &'static str {
+    match self {
+      // Bun has no strict offline mode, so this is the closest equivalent.
+      PackageManager::Bun => "--prefer-offline",
+      PackageManager::Pnpm | PackageManager::Npm | PackageManager::Yarn => "--offline",
+    }
+  }
+
+  /// The flag to prefer the local cache, falling back to the network.
+  pub fn prefer_offline_flag(self) -> &'static str {
+    "--prefer-offline"
+  }
+
+  /// Whether the workspace's packages are listed in `pnpm-workspace.yaml` rather than
+  /// the `workspaces` field of the root manifest.
+  pub fn uses_workspace_file(self) -> bool {
+    self == PackageManager::Pnpm
+  }
+}
+
+impl fmt::Display for PackageManager {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.name())
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_detect() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    assert_eq!(PackageManager::detect(root, None), PackageManager::Pnpm);
+
+    std::fs::write(root.join("package-lock.json"), "{}").unwrap();
+    assert_eq!(PackageManager::detect(root, None), PackageManager::Npm);
+    assert_eq!(
+      PackageManager::detect(root, Some("yarn@1.22.22+sha256.abc")),
+      PackageManager::Yarn
+    );
+    assert_eq!(
+      PackageManager::detect(root, Some("deno@2")),
+      PackageManager::Npm
+    );
+  }
+
+  #[test]
+  fn test_resolve() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let resolve = |field: &str| {
+      let manifest = package_json_schema::PackageJson::try_from(format!(
+        r#"{{"packageManager": "{field}", "depot": {{"depot-version": "0.0.0"}}}}"#
+      ))?;
+      let manifest = WorkspaceManifest::from_json(manifest, &dir.path().join("package.json"))?;
+      PackageManager::resolve(dir.path(), &manifest)
+    };
+    assert_eq!(resolve("yarn@1.22.22")?, PackageManager::Yarn);
+    assert_eq!(resolve("pnpm@10.4.1")?, PackageManager::Pnpm);
+    assert!(resolve("yarn@4.1.0+sha256.abc").is_err());
+    Ok(())
+  }
+
+  #[test]
+  fn test_args() {
+    let args = ["--watch".to_string()];
+    assert_eq!(
+      PackageManager::Npm.run_args("gen", &args),
+      ["run", "gen", "--", "--watch"]
+    );
+    assert_eq!(PackageManager::Bun.run_args("gen", &[]), ["run", "gen"]);
+    assert_eq!(PackageManager::Bun.exec_args(), None);
+    assert_eq!(
+      PackageManager::Pnpm.add_args(DependencyKind::Dev, true),
+      ["add", "--save-dev", "--workspace-root"]
+    );
+    assert_eq!(
+      PackageManager::Npm.add_args(DependencyKind::Peer, true),
+      ["install", "--save-peer"]
+    );
+  }
+}
@@ -1,13 +1,12 @@
 use futures::future::join_all;
 use std::{
   process::{ExitStatus, Stdio},
-  sync::{
-    Arc, Mutex, MutexGuard,
-    atomic::{AtomicBool, Ordering},
-  },
+  sync::{Arc, Mutex, MutexGuard, atomic::Ordering},
+  time::{Duration, Instant},
 };
 use tokio::{
   io::{AsyncBufReadExt, AsyncRead, BufReader},
+  sync::Notify,
   task::JoinHandle,
 };
 
@@ -31,14 +30,44 @@ pub struct LogLine {
 
 pub type LogBuffer = RingBuffer<LogLine>;
 
+/// Called on each line of stdout as it is read, returning the line to show in the logs.
+pub type LineFilter = Arc<dyn Fn(String) -> String + Send + Sync>;
+
+/// The lifecycle state of a [`Process`].
+#[atomic_enum::atomic_enum]
+#[derive(PartialEq)]
+pub enum ProcessStatus {
+  Running = 0,
+  Succeeded,
+  Failed,
+  /// Exited unexpectedly and is waiting to be restarted.
+  Crashed,
+}
+
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+
+/// A process that runs at least this long before crashing is considered to have been healthy,
+/// so its restart count and backoff are reset.
+const HEALTHY_RUNTIME: Duration = Duration::from_mins(1);
+
 /// Encapsulates shell commands.
 ///
-/// Wrapper around [`tokio::process::Command`] that deals with I/O.
+/// Wrapper around [`tokio::process::Command`] that deals with I/O. The command is kept
+/// so that the process can be restarted with the same configuration. A process without
+/// a command only holds logs, for work that Depot does itself.
 pub struct Process {
   script: String,
+  cmd: Mutex<Option<tokio::process::Command>>,
   child: Mutex<Option<tokio::process::Child>>,
+  /// The process group of the last run, which also holds the processes it started.
+  group: Mutex<Option<u32>>,
   logs: Arc<Mutex<LogBuffer>>,
-  finished: AtomicBool,
+  filter: Option<LineFilter>,
+  status: AtomicProcessStatus,
+  started: Mutex<Instant>,
+  duration: Mutex<Option<Duration>>,
+  restart_requested: Notify,
 
   // TODO: is it necessary to abort these handles?
   #[allow(unused)]
@@ -46,42 +75,92 @@ pub struct Process {
 }
 
 impl Process {
-  pub fn new(script: String, mut cmd: tokio::process::Command) -> Result<Self> {
+  pub fn new(script: String, cmd: tokio::process::Command) -> Result<Self> {
+    Self::with_filter(script, cmd, None)
+  }
+
+  /// Creates a process whose stdout passes through `filter` before reaching the logs.
+  pub fn with_filter(
+    script: String,
+    mut cmd: tokio::process::Command,
+    filter: Option<LineFilter>,
+  ) -> Result<Self> {
+    Self::capture_output(&mut cmd);
+    let process = Self::log(script, Some(cmd), filter);
+    process.spawn()?;
+    Ok(process)
+  }
+
+  /// Creates a process that only shows lines added with [`Process::push_line`], and
+  /// runs until [`Process::finish`] is called.
+  pub fn log_only(script: String) -> Self {
+    Self::log(script, None, None)
+  }
+
+  fn log(script: String, cmd: Option<tokio::process::Command>, filter: Option<LineFilter>) -> Self {
+    Process {
+      script,
+      cmd: Mutex::new(cmd),
+      child: Mutex::new(None),
+      group: Mutex::new(None),
+      logs: Arc::new(Mutex::new(RingBuffer::new())),
+      filter,
+      status: AtomicProcessStatus::new(ProcessStatus::Running),
+      started: Mutex::new(Instant::now()),
+      duration: Mutex::new(None),
+      restart_requested: Notify::new(),
+      pipe_handles: Mutex::new(Vec::new()),
+    }
+  }
+
+  fn capture_output(cmd: &mut tokio::process::Command) {
     cmd.kill_on_drop(true);
+    // Tools are started through the package manager, so killing only the direct child
+    // would leave the tool running. A new process group lets them be killed together.
+    #[cfg(unix)]
+    cmd.process_group(0);
     cmd.stdout(Stdio::piped());
     cmd.stderr(Stdio::piped());
+  }
 
-    let mut child = cmd
+  fn spawn(&self) -> Result<()> {
+    let mut child = self
+      .cmd
+      .lock()
+      .unwrap()
+      .as_mut()
+      .with_context(|| format!("Process `{}` has no command", self.script))?
       .spawn()
-      .with_context(|| format!("Failed to spawn process: `{script}`"))?;
+      .with_context(|| format!("Failed to spawn process: `{}`", self.script))?;
 
-    let logs: Arc<Mutex<RingBuffer<LogLine>>> = Arc::new(Mutex::new(RingBuffer::new()));
-    let pipe_handles = vec![
+    *self.pipe_handles.lock().unwrap() = vec![
       tokio::spawn(Self::pipe_stdio(
         child.stdout.take().unwrap(),
-        logs.clone(),
+        self.logs.clone(),
         OutputChannel::Stdout,
+        self.filter.clone(),
       )),
       tokio::spawn(Self::pipe_stdio(
         child.stderr.take().unwrap(),
-        logs.clone(),
+        self.logs.clone(),
         OutputChannel::Stderr,
+        None,
       )),
     ];
+    *self.group.lock().unwrap() = child.id();
+    *self.child.lock().unwrap() = Some(child);
+    *self.started.lock().unwrap() = Instant::now();
+    *self.duration.lock().unwrap() = None;
+    self.status.store(ProcessStatus::Running, Ordering::SeqCst);
 
-    Ok(Process {
-      script,
-      child: Mutex::new(Some(child)),
-      logs,
-      finished: AtomicBool::new(false),
-      pipe_handles: Mutex::new(pipe_handles),
-    })
+    Ok(())
   }
 
   async fn pipe_stdio(
     stdio: impl AsyncRead + Unpin,
     buffer: Arc<Mutex<LogBuffer>>,
     channel: OutputChannel,
+    filter: Option<LineFilter>,
   ) {
     let mut lines = BufReader::new(stdio).lines();
     while let Some(line) = lines.next_line().await.unwrap() {
@@ -93,10 +172,46 @@ impl Process {
         }
         None => line,
       };
+      let line = match &filter {
+        Some(filter) => filter(line),
+        None => line,
+      };
       buffer.push(LogLine { line, channel });
     }
   }
 
+  async fn join_pipes(&self) {
+    let pipe_handles = self
+      .pipe_handles
+      .lock()
+      .unwrap()
+      .drain(..)
+      .collect::<Vec<_>>();
+    join_all(pipe_handles).await;
+  }
+
+  /// Kills the process and the processes it started, without waiting for them to exit.
+  pub fn kill(&self) {
+    self.kill_group();
+    if let Some(child) = &mut *self.child.lock().unwrap() {
+      let _ = child.start_kill();
+    }
+  }
+
+  /// Kills every process in the process group of the last run, which outlives the
+  /// direct child if the child exits before its own children.
+  fn kill_group(&self) {
+    #[cfg(unix)]
+    if let Some(group) = *self.group.lock().unwrap()
+      && let Ok(group) = libc::pid_t::try_from(group)
+    {
+      // SAFETY: `killpg` only sends a signal. A group that has already exited is ignored.
+      unsafe {
+        libc::killpg(group, libc::SIGKILL);
+      }
+    }
+  }
+
   pub fn script(&self) -> &str {
     &self.script
   }
@@ -105,29 +220,172 @@ impl Process {
     self.logs.lock().unwrap()
   }
 
-  pub fn finished(&self) -> bool {
-    self.finished.load(Ordering::SeqCst)
+  /// Adds a line to the logs that was generated by Depot rather than the process.
+  pub fn push_line(&self, line: impl Into<String>) {
+    self.logs.lock().unwrap().push(LogLine {
+      line: line.into(),
+      channel: OutputChannel::Stdout,
+    });
   }
 
-  pub async fn wait(&self) -> Result<ExitStatus> {
-    let mut child = self.child.lock().unwrap().take().unwrap();
+  /// Records that the process has exited. Processes without a command are finished by
+  /// their owner.
+  pub fn finish(&self, succeeded: bool) {
+    *self.duration.lock().unwrap() = Some(self.started.lock().unwrap().elapsed());
+    self.status.store(
+      if succeeded {
+        ProcessStatus::Succeeded
+      } else {
+        ProcessStatus::Failed
+      },
+      Ordering::SeqCst,
+    );
+  }
 
-    let status_res = child
-      .wait()
-      .await
-      .with_context(|| format!("Process `{}` failed", self.script));
+  pub fn status(&self) -> ProcessStatus {
+    self.status.load(Ordering::SeqCst)
+  }
 
-    let pipe_handles = self
-      .pipe_handles
+  pub fn finished(&self) -> bool {
+    matches!(
+      self.status(),
+      ProcessStatus::Succeeded | ProcessStatus::Failed
+    )
+  }
+
+  /// Time the process has been running, or its total runtime if it has finished.
+  pub fn duration(&self) -> Duration {
+    self
+      .duration
       .lock()
       .unwrap()
-      .drain(..)
-      .collect::<Vec<_>>();
-    join_all(pipe_handles).await;
+      .unwrap_or_else(|| self.started.lock().unwrap().elapsed())
+  }
 
-    self.finished.store(true, Ordering::SeqCst);
+  /// Kills the process if it is still running, and starts it again from its original command.
+  ///
+  /// Logs from previous runs are kept. If the process already finished, then the new run
+  /// is waited on in the background. If it crashed and is waiting to be restarted, then
+  /// its supervisor restarts it right away.
+  pub fn restart(self: &Arc<Self>) -> Result<()> {
+    if self.cmd.lock().unwrap().is_none() {
+      return Ok(());
+    }
+    self.push_line(format!(
+      "\u{1b}[2m─── restarting {} ───\u{1b}[0m",
+      self.script
+    ));
+    if self.finished() {
+      self.spawn()?;
+      let this = Arc::clone(self);
+      tokio::spawn(async move {
+        let _ = this.wait().await;
+      });
+    } else {
+      self.restart_requested.notify_one();
+    }
+    Ok(())
+  }
+
+  /// Runs a finished process again with a new command, clearing the logs of the previous run.
+  pub fn respawn(&self, mut cmd: tokio::process::Command) -> Result<()> {
+    ensure!(
+      self.finished(),
+      "Process `{}` is still running",
+      self.script
+    );
+    Self::capture_output(&mut cmd);
+    *self.cmd.lock().unwrap() = Some(cmd);
+    self.logs.lock().unwrap().clear();
+    self.spawn()
+  }
 
-    status_res
+  pub async fn wait(&self) -> Result<ExitStatus> {
+    loop {
+      let Some(mut child) = self.child.lock().unwrap().take() else {
+        bail!(
+          "Process `{}` is not running or is already being waited on",
+          self.script
+        );
+      };
+
+      let status_res = tokio::select! {
+        status = child.wait() => status.with_context(|| format!("Process `{}` failed", self.script)),
+        () = self.restart_requested.notified() => {
+          self.kill_group();
+          child
+            .kill()
+            .await
+            .with_context(|| format!("Failed to kill process `{}`", self.script))?;
+          self.join_pipes().await;
+          self.spawn()?;
+          continue;
+        }
+      };
+
+      self.join_pipes().await;
+
+      self.finish(matches!(&status_res, Ok(status) if status.success()));
+
+      return status_res;
+    }
+  }
+
+  /// Waits on a process that is expected to run forever, like a watcher or dev server.
+  ///
+  /// Whenever the process exits, a crash banner is added to its logs and the process is
+  /// restarted with exponential backoff. Fails after `max_restarts` crashes in a row.
+  pub async fn supervise(&self, max_restarts: u32) -> Result<()> {
+    self.restart_until(max_restarts, false).await
+  }
+
+  /// Waits on a process that may finish on its own, like a build script. A successful exit
+  /// ends the process, while failures are restarted as in [`Process::supervise`].
+  pub async fn retry(&self, max_restarts: u32) -> Result<()> {
+    self.restart_until(max_restarts, true).await
+  }
+
+  async fn restart_until(&self, max_restarts: u32, stop_on_success: bool) -> Result<()> {
+    let mut crashes = 0;
+    let mut backoff = INITIAL_RESTART_BACKOFF;
+    loop {
+      let status = self.wait().await?;
+      if stop_on_success && status.success() {
+        return Ok(());
+      }
+      if self.duration() >= HEALTHY_RUNTIME {
+        crashes = 0;
+        backoff = INITIAL_RESTART_BACKOFF;
+      }
+      crashes += 1;
+
+      let reason = match status.code() {
+        Some(code) => format!("exit code {code}"),
+        None => "signal".to_string(),
+      };
+      if crashes > max_restarts {
+        self.push_line(format!(
+          "\u{1b}[1;31m✗ `{}` crashed ({reason}), giving up after {max_restarts} restarts\u{1b}[0m",
+          self.script
+        ));
+        bail!("Process `{}` crashed {crashes} times in a row", self.script);
+      }
+
+      self.status.store(ProcessStatus::Crashed, Ordering::SeqCst);
+      self.push_line(format!(
+        "\u{1b}[1;31m✗ `{}` crashed ({reason}), restarting in {:.1}s (attempt {crashes}/{max_restarts})\u{1b}[0m",
+        self.script,
+        backoff.as_secs_f64()
+      ));
+      // A restart requested during the backoff is handled here, rather than killing
+      // the next run as soon as it starts.
+      tokio::select! {
+        () = tokio::time::sleep(backoff) => {}
+        () = self.restart_requested.notified() => {}
+      }
+      backoff = (backoff * 2).min(MAX_RESTART_BACKOFF);
+      self.spawn()?;
+    }
   }
 
   pub async fn wait_for_success(&self) -> Result<()> {
@@ -144,6 +402,15 @@ impl Process {
   }
 }
 
+impl Drop for Process {
+  fn drop(&mut self) {
+    // `kill_on_drop` only kills the direct child, not the tool it started.
+    if !self.finished() {
+      self.kill_group();
+    }
+  }
+}
+
 #[cfg(test)]
 mod test {
   use tokio::process::Command;
@@ -160,6 +427,7 @@ mod test {
 
     let status = process.wait().await?;
     assert!(status.success());
+    assert!(process.status() == ProcessStatus::Succeeded);
 
     let stdout = process
       .stdout()
@@ -172,12 +440,178 @@ mod test {
     Ok(())
   }
 
+  #[tokio::test]
+  async fn process_restart() -> Result<()> {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", "echo started; exec sleep 10"]);
+
+    let process = Arc::new(Process::new("sh".to_string(), cmd)?);
+    let waiter = tokio::spawn({
+      let process = Arc::clone(&process);
+      async move { process.wait().await }
+    });
+
+    while process.stdout().is_empty() {
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    process.restart()?;
+    while process.stdout().len() < 3 {
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    assert!(!process.finished());
+
+    let lines = process
+      .stdout()
+      .iter()
+      .map(|line| line.line.clone())
+      .collect::<Vec<_>>();
+    assert_eq!(lines[0], "started");
+    assert!(lines[1].contains("restarting sh"));
+    assert_eq!(lines[2], "started");
+
+    waiter.abort();
+    Ok(())
+  }
+
+  #[cfg(target_os = "linux")]
+  #[tokio::test]
+  async fn process_kill_group() -> Result<()> {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", "sleep 30 & echo $!; wait"]);
+
+    let process = Process::new("sh".to_string(), cmd)?;
+    while process.stdout().is_empty() {
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let pid = process.stdout().iter().next().unwrap().line.clone();
+    process.kill();
+
+    // A killed process may stay a zombie until it is reaped.
+    let alive = || {
+      std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+        stat
+          .split_whitespace()
+          .nth(2)
+          .is_none_or(|state| state != "Z")
+      })
+    };
+    for _ in 0..100 {
+      if !alive() {
+        assert!(!process.wait().await?.success());
+        return Ok(());
+      }
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    bail!("`sleep` is still running after its parent was killed");
+  }
+
+  #[tokio::test]
+  async fn process_restart_while_crashed() -> Result<()> {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", "echo run; exit 1"]);
+
+    let process = Arc::new(Process::new("sh".to_string(), cmd)?);
+    let supervisor = tokio::spawn({
+      let process = Arc::clone(&process);
+      async move { process.supervise(5).await }
+    });
+
+    while process.status() != ProcessStatus::Crashed {
+      tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    process.restart()?;
+
+    // The restart skips the backoff, and the next run isn't killed by a stale request.
+    tokio::time::sleep(Duration::from_millis(400)).await;
+    let runs = process
+      .stdout()
+      .iter()
+      .filter(|line| line.line == "run")
+      .count();
+    assert_eq!(runs, 2);
+
+    supervisor.abort();
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn process_wait_twice() -> Result<()> {
+    let process = Process::new("true".to_string(), Command::new("true"))?;
+    process.wait().await?;
+    assert!(process.wait().await.is_err());
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn process_supervise() -> Result<()> {
+    let mut cmd = Command::new("sh");
+    cmd.args(["-c", "echo crash; exit 1"]);
+
+    let process = Process::new("sh".to_string(), cmd)?;
+    assert!(process.supervise(1).await.is_err());
+    assert!(process.status() == ProcessStatus::Failed);
+
+    let lines = process
+      .stdout()
+      .iter()
+      .map(|line| line.line.clone())
+      .collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "crash");
+    assert!(lines[1].contains("restarting in 0.5s (attempt 1/1)"));
+    assert_eq!(lines[2], "crash");
+    assert!(lines[3].contains("giving up after 1 restarts"));
+
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn process_retry() -> Result<()> {
+    let mut cmd = Command::new("echo");
+    cmd.arg("done");
+
+    let process = Process::new("echo".to_string(), cmd)?;
+    process.retry(1).await?;
+    assert!(process.status() == ProcessStatus::Succeeded);
+    assert_eq!(process.stdout().len(), 1);
+
+    let process = Process::new("false".to_string(), Command::new("false"))?;
+    assert!(process.retry(1).await.is_err());
+    assert!(process.status() == ProcessStatus::Failed);
+
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn process_respawn() -> Result<()> {
+    let mut cmd = Command::new("echo");
+    cmd.arg("first");
+    let process = Process::new("echo".to_string(), cmd)?;
+    assert!(process.respawn(Command::new("true")).is_err());
+    process.wait().await?;
+
+    let mut cmd = Command::new("echo");
+    cmd.arg("second");
+    process.respawn(cmd)?;
+    process.wait().await?;
+
+    let lines = process
+      .stdout()
+      .iter()
+      .map(|line| line.line.clone())
+      .collect::<Vec<_>>();
+    assert_eq!(lines, ["second"]);
+
+    Ok(())
+  }
+
   #[tokio::test]
   async fn process_fail() -> Result<()> {
     let cmd = Command::new("false");
     let process = Process::new("false".to_string(), cmd)?;
     let status = process.wait().await?;
     assert!(!status.success());
+    assert!(process.status() == ProcessStatus::Failed);
     Ok(())
   }
 }
@@ -0,0 +1,169 @@
+use anyhow::{Result, bail};
+use indexmap::IndexMap;
+use serde_json::Value;
+
+pub const DEFAULT_PROFILE: &str = "dev";
+pub const RELEASE_PROFILE: &str = "release";
+
+/// A build profile as written under `depot.profiles` in the workspace manifest.
+///
+/// Unset fields are taken from the profile named by `inherits`, which defaults to the
+/// built-in profile of the same name if there is one, and `dev` otherwise.
+#[derive(serde::Serialize, serde::Deserialize, Default, Clone)]
+#[serde(rename_all = "kebab-case")]
+pub struct ProfileConfig {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub inherits: Option<String>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub minify: Option<bool>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub sourcemap: Option<bool>,
+
+  /// The Vite mode, which also selects the `.env.[mode]` files that Vite loads.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub mode: Option<String>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub node_env: Option<String>,
+
+  /// Global constants replaced at build time, merged with the inherited ones.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub define: Option<IndexMap<String, Value>>,
+}
+
+/// A fully resolved build profile.
+#[derive(Clone, Debug, PartialEq, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+  pub name: String,
+  pub minify: bool,
+  pub sourcemap: bool,
+  pub mode: String,
+  pub node_env: Option<String>,
+  pub define: IndexMap<String, Value>,
+}
+
+impl Profile {
+  fn builtin(name: &str) -> Option<Self> {
+    let (minify, sourcemap, mode) = match name {
+      DEFAULT_PROFILE => (false, true, "development"),
+      RELEASE_PROFILE => (true, false, "production"),
+      _ => return None,
+    };
+    Some(Profile {
+      name: name.to_string(),
+      minify,
+      sourcemap,
+      mode: mode.to_string(),
+      node_env: None,
+      define: IndexMap::new(),
+    })
+  }
+
+  /// Looks up the profile `name` from the built-in profiles and those in `configs`.
+  pub fn resolve(name: &str, configs: &IndexMap<String, ProfileConfig>) -> Result<Self> {
+    Self::resolve_chain(name, configs, &mut Vec::new())
+  }
+
+  fn resolve_chain<'a>(
+    name: &'a str,
+    configs: &'a IndexMap<String, ProfileConfig>,
+    seen: &mut Vec<&'a str>,
+  ) -> Result<Self> {
+    if seen.contains(&name) {
+      bail!("Profile `{name}` inherits from itself");
+    }
+    seen.push(name);
+
+    let Some(config) = configs.get(name) else {
+      return match Self::builtin(name) {
+        Some(profile) => Ok(profile),
+        None => bail!("Unknown profile: `{name}`"),
+      };
+    };
+
+    // A config for a built-in profile overrides it rather than starting from `dev`.
+    let builtin = Self::builtin(name);
+    let mut profile = match (&config.inherits, builtin) {
+      (Some(parent), _) => Self::resolve_chain(parent, configs, seen)?,
+      (None, Some(builtin)) => builtin,
+      (None, None) => Self::resolve_chain(DEFAULT_PROFILE, configs, seen)?,
+    };
+
+    profile.name = name.to_string();
+    if let Some(minify) = config.minify {
+      profile.minify = minify;
+    }
+    if let Some(sourcemap) = config.sourcemap {
+      profile.sourcemap = sourcemap;
+    }
+    if let Some(mode) = &config.mode {
+      profile.mode.clone_from(mode);
+    }
+    if let Some(node_env) = &config.node_env {
+      profile.node_env = Some(node_env.clone());
+    }
+    if let Some(define) = &config.define {
+      profile.define.extend(define.clone());
+    }
+    Ok(profile)
+  }
+
+  /// True if this is a production build, like `release` and profiles that inherit from it.
+  pub fn is_production(&self) -> bool {
+    self.mode == "production"
+  }
+
+  /// The `define` values as a JSON object of Javascript expressions, as expected by Vite.
+  pub fn define_json(&self) -> String {
+    let exprs = self
+      .define
+      .iter()
+      .map(|(key, value)| (key.clone(), Value::String(value.to_string())))
+      .collect::<serde_json::Map<_, _>>();
+    Value::Object(exprs).to_string()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_resolve_profile() {
+    let configs: IndexMap<String, ProfileConfig> = serde_json::from_value(serde_json::json!({
+      "staging": {
+        "inherits": "release",
+        "sourcemap": true,
+        "define": {"API_URL": "https://staging.example.com"}
+      },
+      "release": {"node-env": "production"},
+      "loop": {"inherits": "loop2"},
+      "loop2": {"inherits": "loop"}
+    }))
+    .unwrap();
+
+    let dev = Profile::resolve("dev", &configs).unwrap();
+    assert!(dev.sourcemap && !dev.minify);
+    assert_eq!(dev.mode, "development");
+
+    let release = Profile::resolve("release", &configs).unwrap();
+    assert!(release.minify && !release.sourcemap);
+    assert_eq!(release.node_env.as_deref(), Some("production"));
+
+    let staging = Profile::resolve("staging", &configs).unwrap();
+    assert!(staging.minify && staging.sourcemap);
+    assert_eq!(staging.mode, "production");
+    assert!(staging.is_production() && !dev.is_production());
+    assert_eq!(staging.node_env.as_deref(), Some("production"));
+    assert_eq!(
+      staging.define_json(),
+      r#"{"API_URL":"\"https://staging.example.com\""}"#
+    );
+
+    assert!(Profile::resolve("missing", &configs).is_err());
+    assert!(Profile::resolve("loop", &configs).is_err());
+  }
+}
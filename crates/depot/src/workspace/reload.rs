@@ -0,0 +1,263 @@
+use anyhow::{Context, Result, anyhow};
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{DebounceEventResult, Debouncer};
+use std::{
+  collections::HashSet,
+  path::{Path, PathBuf},
+  time::Duration,
+};
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use super::{
+  Workspace,
+  package::{Package, PackageName},
+};
+
+/// Files which define the structure of the workspace.
+const MANIFEST_FILES: &[&str] = &["package.json", "pnpm-workspace.yaml"];
+
+/// Files which configure the tools run inside a package.
+const CONFIG_FILES: &[&str] = &[
+  "tsconfig.json",
+  "biome.json",
+  "eslint.config.mjs",
+  ".prettierrc.json",
+  "vite.config.ts",
+  "vite.config.mts",
+  "vitest.config.ts",
+  "vitest.config.mts",
+];
+
+/// A change to the workspace detected by a [`WorkspaceWatcher`].
+pub enum WorkspaceChange {
+  /// A manifest changed or a package was added or removed.
+  Manifest,
+
+  /// A config file changed which affects the processes of these packages.
+  Config(Vec<Package>),
+}
+
+/// Watches the manifests and config files of a workspace during watch mode.
+pub struct WorkspaceWatcher {
+  ws: Workspace,
+  root: PathBuf,
+  rx: UnboundedReceiver<DebounceEventResult>,
+  _debouncer: Debouncer<RecommendedWatcher>,
+}
+
+impl WorkspaceWatcher {
+  pub fn new(ws: &Workspace) -> Result<Self> {
+    let (tx, rx) = unbounded_channel();
+    let timeout = Duration::from_secs(1);
+    let mut debouncer = notify_debouncer_mini::new_debouncer(timeout, move |events| {
+      let _ = tx.send(events);
+    })?;
+
+    let watcher = debouncer.watcher();
+    watcher.watch(&ws.root, RecursiveMode::NonRecursive)?;
+    if ws.monorepo {
+      // Watch every directory under `packages/`, not just loaded packages, so that a
+      // package.json created in a new directory is noticed.
+      let pkg_dir = ws.root.join("packages");
+      watcher.watch(&pkg_dir, RecursiveMode::NonRecursive)?;
+      for entry in pkg_dir.read_dir()? {
+        let path = entry?.path();
+        if path.is_dir() {
+          watcher.watch(&path, RecursiveMode::NonRecursive)?;
+        }
+      }
+    }
+
+    Ok(WorkspaceWatcher {
+      ws: ws.clone(),
+      root: ws.root.canonicalize()?,
+      rx,
+      _debouncer: debouncer,
+    })
+  }
+
+  /// Waits until a manifest or config file changes.
+  pub async fn next_change(&mut self) -> Result<WorkspaceChange> {
+    loop {
+      let events = self
+        .rx
+        .recv()
+        .await
+        .context("Workspace watcher stopped")?
+        .map_err(|e| anyhow!("File watch errors: {e:?}"))?;
+
+      let mut affected: Vec<Package> = Vec::new();
+      for event in events {
+        match self.classify(&event.path) {
+          Some(WorkspaceChange::Manifest) => return Ok(WorkspaceChange::Manifest),
+          Some(WorkspaceChange::Config(pkgs)) => {
+            for pkg in pkgs {
+              if !affected.contains(&pkg) {
+                affected.push(pkg);
+              }
+            }
+          }
+          None => {}
+        }
+      }
+
+      if !affected.is_empty() {
+        return Ok(WorkspaceChange::Config(affected));
+      }
+    }
+  }
+
+  fn classify(&self, path: &Path) -> Option<WorkspaceChange> {
+    let file_name = path.file_name()?.to_str()?;
+    // Package roots are canonicalized, so event paths must be too.
+    let parent = path.parent()?.canonicalize().ok()?;
+
+    if MANIFEST_FILES.contains(&file_name) {
+      return Some(WorkspaceChange::Manifest);
+    }
+
+    // A directory was added to or removed from `packages/`.
+    if self.ws.monorepo && parent == self.root.join("packages") && !path.is_file() {
+      return Some(WorkspaceChange::Manifest);
+    }
+
+    if CONFIG_FILES.contains(&file_name) {
+      let pkgs = match self.ws.packages.iter().find(|pkg| pkg.root == parent) {
+        Some(pkg) => vec![pkg.clone()],
+        None if parent == self.root => self.ws.packages.clone(),
+        None => return None,
+      };
+      return Some(WorkspaceChange::Config(pkgs));
+    }
+
+    None
+  }
+}
+
+impl Workspace {
+  /// The root manifest without the dependencies, whose changes only require `init`
+  /// to run again.
+  fn workspace_config(&self) -> serde_json::Value {
+    let mut manifest = self.manifest.manifest.clone();
+    manifest.dependencies = None;
+    manifest.dev_dependencies = None;
+    manifest.peer_dependencies = None;
+    manifest.optional_dependencies = None;
+    serde_json::json!([manifest, self.manifest.config])
+  }
+
+  /// Names of the packages that are unaffected when this workspace is reloaded as `new`,
+  /// so their processes can keep running.
+  ///
+  /// A package is affected if it was added or removed, if its manifest changed, or if it
+  /// depends on an affected package. Every package is affected if the workspace config
+  /// changed.
+  pub(super) fn unchanged_packages(&self, new: &Workspace) -> HashSet<PackageName> {
+    if !self.monorepo || !new.monorepo || self.workspace_config() != new.workspace_config() {
+      return HashSet::new();
+    }
+
+    let mut changed = HashSet::new();
+    for pkg in &new.packages {
+      let old_pkg = self
+        .packages
+        .iter()
+        .find(|old_pkg| old_pkg.name == pkg.name);
+      let unchanged = old_pkg.is_some_and(|old_pkg| {
+        old_pkg.root == pkg.root && old_pkg.manifest.to_value() == pkg.manifest.to_value()
+      });
+      if !unchanged {
+        changed.insert(pkg.name.clone());
+      }
+    }
+    for pkg in &self.packages {
+      if !new.packages.iter().any(|new_pkg| new_pkg.name == pkg.name) {
+        changed.insert(pkg.name.clone());
+      }
+    }
+
+    let dependents = |ws: &Workspace| {
+      ws.pkg_graph
+        .nodes()
+        .filter(|pkg| {
+          ws.pkg_graph
+            .all_deps_for(pkg)
+            .any(|dep| changed.contains(&dep.name))
+        })
+        .map(|pkg| pkg.name.clone())
+        .collect::<Vec<_>>()
+    };
+    let affected = [dependents(self), dependents(new)].concat();
+    changed.extend(affected);
+
+    new
+      .packages
+      .iter()
+      .map(|pkg| pkg.name.clone())
+      .filter(|name| !changed.contains(name))
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use std::fs;
+
+  use serde_json::json;
+
+  use super::*;
+  use crate::CommonArgs;
+
+  fn write_manifest(path: &Path, manifest: &serde_json::Value) -> Result<()> {
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(path, manifest.to_string())?;
+    Ok(())
+  }
+
+  #[tokio::test]
+  async fn test_unchanged_packages() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path().canonicalize()?;
+    let ws_manifest = json!({"depot": {"depot-version": crate::workspace::DEPOT_VERSION}});
+    write_manifest(&root.join("package.json"), &ws_manifest)?;
+    let pkg_manifest = |name: &str, deps: serde_json::Value| json!({"name": name, "dependencies": deps, "depot": {"target": "lib", "platform": "browser"}});
+    for (name, deps) in [
+      ("a", json!({})),
+      ("b", json!({"a": "workspace:^"})),
+      ("c", json!({})),
+    ] {
+      write_manifest(
+        &root.join("packages").join(name).join("package.json"),
+        &pkg_manifest(name, deps),
+      )?;
+    }
+
+    let load = || Workspace::load(Some(root.clone()), CommonArgs::default());
+    let ws = load().await?;
+    let names = |names: &[&str]| -> HashSet<PackageName> {
+      names.iter().map(|name| name.parse().unwrap()).collect()
+    };
+    assert_eq!(
+      ws.unchanged_packages(&load().await?),
+      names(&["a", "b", "c"])
+    );
+
+    // Dependents of a changed package are affected too.
+    write_manifest(
+      &root.join("packages/a/package.json"),
+      &pkg_manifest("a", json!({"react": "^19"})),
+    )?;
+    let new_ws = load().await?;
+    assert_eq!(ws.unchanged_packages(&new_ws), names(&["c"]));
+
+    // Changing the workspace config affects every package.
+    let ws = new_ws;
+    write_manifest(
+      &root.join("package.json"),
+      &json!({"depot": {"depot-version": crate::workspace::DEPOT_VERSION, "max-restarts": 1}}),
+    )?;
+    assert!(ws.unchanged_packages(&load().await?).is_empty());
+
+    Ok(())
+  }
+}
@@ -1,41 +1,73 @@
 use anyhow::Result;
 
 use futures::{FutureExt, future::BoxFuture};
-use log::debug;
+use log::{debug, warn};
 use std::{
   cell::RefCell,
-  collections::HashMap,
-  future::Future,
-  sync::{Arc, atomic::Ordering},
+  collections::{HashMap, HashSet},
+  future::{self, Future},
+  sync::{Arc, Mutex, atomic::Ordering},
+};
+use tokio::{
+  sync::{Notify, watch},
+  task::{AbortHandle, JoinHandle},
 };
-use tokio::sync::Notify;
 
 use crate::{
-  logger::ui::{FullscreenRenderer, InlineRenderer, Renderer},
+  commands::custom,
+  logger::ui::{FullscreenRenderer, InlineRenderer, PlainRenderer, Renderer},
   shareable,
 };
 
 use super::{
   Command, CommandGraph, CommandInner, CommandRuntime, Workspace, build_command_graph,
   dep_graph::DepGraph,
+  package::{Package, PackageName},
+  reload::{WorkspaceChange, WorkspaceWatcher},
 };
 
+/// The lifecycle state of a [`Task`].
 #[atomic_enum::atomic_enum]
 #[derive(PartialEq)]
-enum TaskStatus {
+pub enum TaskStatus {
   Pending = 0,
   Running,
   Finished,
+  Failed,
 }
 
 type TaskFuture = Box<dyn FnOnce() -> BoxFuture<'static, (Result<()>, Task)>>;
 
+type TaskHandle = JoinHandle<(Result<()>, Task)>;
+
+/// Tasks that keep running when the workspace is reloaded, keyed by [`TaskInner::key`].
+type CarriedTasks = HashMap<String, TaskHandle>;
+
+/// Aborts a task when dropped.
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+  fn drop(&mut self) {
+    self.0.abort();
+  }
+}
+
+/// Waits on a task that was started before the workspace was reloaded, which is aborted
+/// if this future is dropped.
+async fn resume(handle: TaskHandle) -> Result<()> {
+  let _guard = AbortOnDrop(handle.abort_handle());
+  let (result, _) = handle.await?;
+  result
+}
+
 pub struct TaskInner {
   key: String,
   command: Command,
+  pkg: Option<Package>,
   deps: Vec<String>,
   status: AtomicTaskStatus,
   can_skip: bool,
+  error: Mutex<Option<String>>,
 }
 
 shareable!(Task, TaskInner);
@@ -44,6 +76,7 @@ impl Task {
   fn make<F: Future<Output = Result<()>> + Send + 'static>(
     key: String,
     command: Command,
+    pkg: Option<Package>,
     fut: F,
     deps: Vec<String>,
     can_skip: bool,
@@ -51,9 +84,11 @@ impl Task {
     let task = Task::new(TaskInner {
       key,
       command,
+      pkg,
       deps,
       can_skip,
       status: AtomicTaskStatus::new(TaskStatus::Pending),
+      error: Mutex::new(None),
     });
     let task2 = task.clone();
     let boxed_fut = Box::new(move || {
@@ -68,41 +103,134 @@ impl Task {
 }
 
 impl TaskInner {
-  fn key(&self) -> &str {
+  pub fn key(&self) -> &str {
     &self.key
   }
 
-  fn status(&self) -> TaskStatus {
+  pub fn command(&self) -> &Command {
+    &self.command
+  }
+
+  /// The package this task runs on, or `None` for workspace commands.
+  pub fn package(&self) -> Option<&Package> {
+    self.pkg.as_ref()
+  }
+
+  pub fn status(&self) -> TaskStatus {
     self.status.load(Ordering::SeqCst)
   }
+
+  /// The error that the task failed with, if it failed.
+  pub fn error(&self) -> Option<String> {
+    self.error.lock().unwrap().clone()
+  }
+}
+
+/// The outcome of a [`Task`] in a [`RunReport`].
+#[derive(Debug)]
+pub struct TaskReport {
+  pub key: String,
+  pub command: String,
+  pub package: Option<PackageName>,
+  pub status: TaskStatus,
+  pub error: Option<String>,
+}
+
+impl TaskReport {
+  fn new(task: &Task) -> Self {
+    TaskReport {
+      key: task.key.clone(),
+      command: task.command.name(),
+      package: task.pkg.as_ref().map(|pkg| pkg.name.clone()),
+      status: task.status(),
+      error: task.error(),
+    }
+  }
+}
+
+/// The result of [`Workspace::run_with_renderer`].
+#[derive(Debug)]
+pub struct RunReport {
+  /// Every task in the run, in no particular order. Tasks that never started because
+  /// a dependency failed are still [`TaskStatus::Pending`].
+  pub tasks: Vec<TaskReport>,
+
+  /// The error that stopped the run, if it did not succeed.
+  pub error: Option<anyhow::Error>,
+}
+
+impl RunReport {
+  pub fn succeeded(&self) -> bool {
+    self.error.is_none()
+  }
+
+  /// Converts the report into the error that stopped the run, if any.
+  pub fn into_result(self) -> Result<()> {
+    match self.error {
+      Some(e) => Err(e),
+      None => Ok(()),
+    }
+  }
 }
 
 type TaskGraph = DepGraph<Task>;
 
+/// Starts every pending task whose dependencies have finished, or marks it finished if
+/// it can be skipped. Returns true if any task changed status.
+#[allow(clippy::mutable_key_type)]
+fn start_ready_tasks(
+  task_graph: &TaskGraph,
+  task_futures: &mut HashMap<Task, TaskFuture>,
+  running_futures: &mut Vec<(Task, TaskHandle)>,
+) -> bool {
+  let pending = task_graph
+    .nodes()
+    .filter(|task| task.status() == TaskStatus::Pending);
+  let mut progressed = false;
+  for task in pending {
+    let imm_deps = task_graph.immediate_deps_for(task).collect::<Vec<_>>();
+    let deps_finished = imm_deps
+      .iter()
+      .all(|dep| dep.status() == TaskStatus::Finished);
+    if deps_finished {
+      let can_skip = task.can_skip && imm_deps.iter().all(|dep| dep.can_skip);
+      let task_fut = task_futures.remove(task).unwrap();
+      progressed = true;
+      if can_skip {
+        task.status.store(TaskStatus::Finished, Ordering::SeqCst);
+      } else {
+        debug!("Starting task for: {}", task.key());
+        task.status.store(TaskStatus::Running, Ordering::SeqCst);
+        running_futures.push((task.clone(), tokio::spawn(task_fut())));
+      }
+    }
+  }
+  progressed
+}
+
+/// Combines the errors of the tasks that failed during a `--keep-going` run.
+fn failure_summary(mut failures: Vec<(String, anyhow::Error)>) -> anyhow::Error {
+  if failures.len() == 1 {
+    return failures.remove(0).1;
+  }
+  let details = failures
+    .iter()
+    .map(|(key, e)| format!("\n  {key}: {e}"))
+    .collect::<String>();
+  anyhow::anyhow!("{} tasks failed:{details}", failures.len())
+}
+
 impl Workspace {
   fn spawn_log_thread(
-    &self,
+    renderer: impl Renderer + 'static,
+    ws: watch::Receiver<Workspace>,
     log_should_exit: &Arc<Notify>,
     runner_should_exit: &Arc<Notify>,
-    runtime: Option<CommandRuntime>,
   ) -> impl Future {
-    let ws = self.clone();
     let log_should_exit = Arc::clone(log_should_exit);
     let runner_should_exit = Arc::clone(runner_should_exit);
-    let use_fullscreen_renderer =
-      !ws.common.no_fullscreen && matches!(runtime, Some(CommandRuntime::RunForever));
     tokio::spawn(async move {
-      let result = if use_fullscreen_renderer {
-        FullscreenRenderer::new()
-          .unwrap()
-          .render_loop(&ws, &log_should_exit)
-          .await
-      } else {
-        InlineRenderer::new()
-          .render_loop(&ws, &log_should_exit)
-          .await
-      };
-      match result {
+      match renderer.render_loop(&ws, &log_should_exit).await {
         Ok(true) => runner_should_exit.notify_one(),
         Ok(false) => {}
         Err(e) => {
@@ -117,28 +245,39 @@ impl Workspace {
     &self,
     cmd_graph: &CommandGraph,
     runtime: Option<CommandRuntime>,
+    reuse_ws_tasks: bool,
+    carried: CarriedTasks,
   ) -> (TaskGraph, HashMap<Task, TaskFuture>) {
+    let carried = RefCell::new(carried);
     let futures = RefCell::new(HashMap::new());
     let task_pool = RefCell::new(HashMap::new());
 
     let tasks_for = |cmd: &Command| -> Vec<Task> {
       macro_rules! add_task {
-        ($key:expr, $task:expr, $deps:expr, $files:expr) => {{
+        ($key:expr, $pkg:expr, $task:expr, $deps:expr, $files:expr) => {{
           task_pool
             .borrow_mut()
             .entry($key.clone())
             .or_insert_with(|| {
-              let can_skip = self.common.incremental
-                && !matches!(runtime, Some(CommandRuntime::RunForever))
-                && match $files {
-                  Some(files) => {
-                    let fingerprints = self.fingerprints.read().unwrap();
-                    fingerprints.can_skip(&$key, files)
-                  }
-                  None => false,
-                };
-
-              let (task, future) = Task::make($key, cmd.clone(), $task, $deps, can_skip);
+              if let Some(handle) = carried.borrow_mut().remove(&$key) {
+                let (task, future) =
+                  Task::make($key, cmd.clone(), $pkg, resume(handle), $deps, false);
+                futures.borrow_mut().insert(task.clone(), future);
+                return task;
+              }
+
+              let can_skip = (reuse_ws_tasks && matches!(&**cmd, CommandInner::Workspace(_)))
+                || (self.common.incremental
+                  && !matches!(runtime, Some(CommandRuntime::RunForever))
+                  && match $files {
+                    Some(files) => {
+                      let fingerprints = self.fingerprints.read().unwrap();
+                      fingerprints.can_skip(&$key, files)
+                    }
+                    None => false,
+                  });
+
+              let (task, future) = Task::make($key, cmd.clone(), $pkg, $task, $deps, can_skip);
               futures.borrow_mut().insert(task.clone(), future);
               task
             })
@@ -159,8 +298,14 @@ impl Workspace {
                 .immediate_deps_for(&pkg)
                 .map(|pkg| pkg_cmd.pkg_key(pkg))
                 .collect();
-              let files = pkg.all_files().collect::<Vec<_>>();
-              add_task!(key, cmd.clone().run_pkg(pkg), deps, Some(files))
+              let files = pkg_cmd.input_files(&pkg);
+              add_task!(
+                key,
+                Some(pkg.clone()),
+                cmd.clone().run_pkg(pkg),
+                deps,
+                Some(files)
+              )
             })
           })
           .collect(),
@@ -169,7 +314,7 @@ impl Workspace {
           let key = ws_cmd.ws_key();
           let deps = vec![];
           let files = ws_cmd.input_files(self);
-          vec![add_task!(key, cmd.clone().run_ws(this), deps, files)]
+          vec![add_task!(key, None, cmd.clone().run_ws(this), deps, files)]
         }
       }
     };
@@ -180,7 +325,15 @@ impl Workspace {
       |task: &Task| {
         let mut deps = cmd_graph
           .immediate_deps_for(&task.command)
-          .flat_map(tasks_for)
+          .flat_map(|(dep, upstream)| {
+            tasks_for(dep).into_iter().filter(move |dep_task| {
+              // An upstream dependency excludes the task's own package.
+              match (upstream, &task.pkg, &dep_task.pkg) {
+                (true, Some(pkg), Some(dep_pkg)) => self.pkg_graph.is_dependent_on(pkg, dep_pkg),
+                _ => true,
+              }
+            })
+          })
           .collect::<Vec<_>>();
         let runtime = task.command.runtime();
         if let Some(CommandRuntime::WaitForDependencies) = runtime {
@@ -194,56 +347,165 @@ impl Workspace {
     (task_graph, futures.into_inner())
   }
 
+  /// Runs `root` and the commands it depends on, displaying progress as selected by
+  /// [`CommonArgs::progress`](crate::CommonArgs).
   pub async fn run(&self, root: Command) -> Result<()> {
+    let use_plain_renderer = self.common.progress.is_plain();
+    let use_fullscreen_renderer = !use_plain_renderer
+      && !self.common.no_fullscreen
+      && matches!(root.runtime(), Some(CommandRuntime::RunForever));
+    let report = if use_plain_renderer {
+      self.run_with_renderer(root, PlainRenderer::new()).await?
+    } else if use_fullscreen_renderer {
+      self
+        .run_with_renderer(root, FullscreenRenderer::new()?)
+        .await?
+    } else {
+      self.run_with_renderer(root, InlineRenderer::new()).await?
+    };
+    report.into_result()
+  }
+
+  /// Runs `root` and the commands it depends on, displaying progress with `renderer`.
+  ///
+  /// Failed tasks are described in the returned report. An error is only returned if
+  /// Depot itself fails, such as when it can't save fingerprints.
+  pub async fn run_with_renderer(
+    &self,
+    root: Command,
+    renderer: impl Renderer + 'static,
+  ) -> Result<RunReport> {
     let runtime = root.runtime();
-    let cmd_graph = build_command_graph(&root);
-    let (task_graph, mut task_futures) = self.build_task_graph(&cmd_graph, runtime);
+    let cmd_graph = build_command_graph(&root, |cmd| {
+      if self.common.no_deps && *cmd == root {
+        Vec::new()
+      } else {
+        custom::command_deps(self, cmd)
+      }
+    });
 
     let log_should_exit: Arc<Notify> = Arc::new(Notify::new());
     let runner_should_exit: Arc<Notify> = Arc::new(Notify::new());
 
+    let (ws_tx, ws_rx) = watch::channel(self.clone());
+    let cleanup_logs =
+      Self::spawn_log_thread(renderer, ws_rx, &log_should_exit, &runner_should_exit);
+
+    let mut ws = self.clone();
+    let mut reuse_ws_tasks = false;
+    let mut carried = CarriedTasks::new();
+    let result = loop {
+      match ws
+        .run_task_graph(
+          &cmd_graph,
+          runtime,
+          reuse_ws_tasks,
+          carried,
+          &runner_should_exit,
+        )
+        .await
+      {
+        Ok(Some((new_ws, new_carried))) => {
+          reuse_ws_tasks = new_ws.dependency_signature() == ws.dependency_signature();
+          ws = new_ws;
+          carried = new_carried;
+          ws_tx.send_replace(ws.clone());
+        }
+        Ok(None) => break Ok(()),
+        Err(e) => break Err(e),
+      }
+    };
+
+    log::debug!("All tasks complete, waiting for log thread to exit");
+    log_should_exit.notify_one();
+    cleanup_logs.await;
+
+    if root.name() != "clean" {
+      ws.fingerprints.read().unwrap().save(&ws.root)?;
+    }
+
+    let tasks = ws.tasks().iter().map(TaskReport::new).collect();
+    Ok(RunReport {
+      tasks,
+      error: result.err(),
+    })
+  }
+
+  /// Runs the tasks for `cmd_graph` on this workspace, resuming the `carried` tasks
+  /// from before a reload rather than starting them again.
+  ///
+  /// In watch mode, returns the reloaded workspace if a manifest changed, along with
+  /// the tasks of unaffected packages, which keep running.
+  async fn run_task_graph(
+    &self,
+    cmd_graph: &CommandGraph,
+    runtime: Option<CommandRuntime>,
+    reuse_ws_tasks: bool,
+    carried: CarriedTasks,
+    runner_should_exit: &Notify,
+  ) -> Result<Option<(Workspace, CarriedTasks)>> {
+    let (task_graph, mut task_futures) =
+      self.build_task_graph(cmd_graph, runtime, reuse_ws_tasks, carried);
+    *self.tasks.write().unwrap() = task_graph.nodes().cloned().collect();
+
     let runner_should_exit_fut = runner_should_exit.notified();
     tokio::pin!(runner_should_exit_fut);
 
-    let cleanup_logs = self.spawn_log_thread(&log_should_exit, &runner_should_exit, runtime);
+    let mut watcher = match runtime {
+      Some(CommandRuntime::RunForever) => Some(WorkspaceWatcher::new(self)?),
+      _ => None,
+    };
 
     let mut running_futures = Vec::new();
+    let mut failures = Vec::new();
     let result = loop {
       let finished = task_graph
         .nodes()
         .all(|task| task.status() == TaskStatus::Finished);
       if finished {
-        break Ok(());
-      }
-
-      let pending = task_graph
-        .nodes()
-        .filter(|task| task.status() == TaskStatus::Pending);
-      for task in pending {
-        let imm_deps = task_graph.immediate_deps_for(task).collect::<Vec<_>>();
-        let deps_finished = imm_deps
-          .iter()
-          .all(|dep| dep.status() == TaskStatus::Finished);
-        if deps_finished {
-          let can_skip = task.can_skip && imm_deps.iter().all(|dep| dep.can_skip);
-          let task_fut = task_futures.remove(task).unwrap();
-          if can_skip {
-            task.status.store(TaskStatus::Finished, Ordering::SeqCst);
-          } else {
-            debug!("Starting task for: {}", task.key());
-            task.status.store(TaskStatus::Running, Ordering::SeqCst);
-            running_futures.push(tokio::spawn(task_fut()));
-          }
-        }
+        break Ok(None);
       }
 
+      let progressed = start_ready_tasks(&task_graph, &mut task_futures, &mut running_futures);
       if running_futures.is_empty() {
-        continue;
+        if progressed {
+          continue;
+        }
+        // With `--keep-going`, every remaining task depends on one that failed.
+        break Err(failure_summary(failures));
       }
 
-      let one_output = futures::future::select_all(&mut running_futures);
+      let one_output =
+        futures::future::select_all(running_futures.iter_mut().map(|(_, handle)| handle));
+      let change = async {
+        match &mut watcher {
+          Some(watcher) => watcher.next_change().await,
+          None => future::pending().await,
+        }
+      };
       let (result, idx, _) = tokio::select! { biased;
-        () = &mut runner_should_exit_fut => break Ok(()),
+        () = &mut runner_should_exit_fut => break Ok(None),
+        change = change => {
+          match change? {
+            WorkspaceChange::Config(pkgs) => {
+              for pkg in pkgs {
+                debug!("Config changed, restarting processes for: {}", pkg.name);
+                for process in pkg.processes().iter() {
+                  process.restart()?;
+                }
+              }
+            }
+            WorkspaceChange::Manifest => match self.reload().await {
+              Ok(ws) => break Ok(Some(ws)),
+              Err(e) => {
+                warn!("Failed to reload workspace: {e:?}");
+                // Pick up any new package directories so their manifests are watched.
+                watcher = Some(WorkspaceWatcher::new(self)?);
+              }
+            },
+          }
+          continue;
+        },
         output = one_output => output,
       };
 
@@ -251,8 +513,20 @@ impl Workspace {
 
       let (result, completed_task) = result?;
 
-      if result.is_err() {
-        break result;
+      if let Err(e) = result {
+        completed_task
+          .status
+          .store(TaskStatus::Failed, Ordering::SeqCst);
+        *completed_task.error.lock().unwrap() = Some(format!("{e:#}"));
+        if !self.common.keep_going {
+          break Err(e);
+        }
+        warn!(
+          "Task failed, continuing with independent tasks: {}",
+          completed_task.key()
+        );
+        failures.push((completed_task.key().to_string(), e));
+        continue;
       }
 
       debug!("Finishing task for: {}", completed_task.key());
@@ -266,22 +540,52 @@ impl Workspace {
         .update_time(completed_task.key().to_string());
     };
 
-    for fut in &mut running_futures {
-      fut.abort();
-    }
-
-    for fut in &mut running_futures {
-      let _ = fut.await;
-    }
+    let Ok(Some(new_ws)) = result else {
+      for (_, handle) in &running_futures {
+        handle.abort();
+      }
+      for (_, handle) in running_futures {
+        let _ = handle.await;
+      }
+      return result.map(|_| None);
+    };
 
-    log::debug!("All tasks complete, waiting for log thread to exit");
-    log_should_exit.notify_one();
-    cleanup_logs.await;
+    let carried = self.carry_over_tasks(&new_ws, running_futures).await;
+    Ok(Some((new_ws, carried)))
+  }
 
-    if root.name() != "clean" {
-      self.fingerprints.read().unwrap().save(&self.root)?;
+  /// Returns the running tasks of packages that are unaffected by reloading this
+  /// workspace as `new_ws`, whose packages take over their processes. Other tasks are
+  /// aborted and their processes killed.
+  async fn carry_over_tasks(
+    &self,
+    new_ws: &Workspace,
+    running_futures: Vec<(Task, TaskHandle)>,
+  ) -> CarriedTasks {
+    let keep = self.unchanged_packages(new_ws);
+    let mut carried = CarriedTasks::new();
+    let mut kept = HashSet::new();
+    for (task, handle) in running_futures {
+      match &task.pkg {
+        Some(pkg) if keep.contains(&pkg.name) => {
+          if kept.insert(pkg.name.clone()) {
+            debug!("Manifest changed, keeping processes for: {}", pkg.name);
+            let successor = new_ws
+              .packages
+              .iter()
+              .find(|new_pkg| new_pkg.name == pkg.name);
+            pkg.hand_over(successor.unwrap());
+          }
+          carried.insert(task.key.clone(), handle);
+        }
+        _ => {
+          handle.abort();
+          let _ = handle.await;
+        }
+      }
     }
+    self.kill_processes(&kept);
 
-    result
+    carried
   }
 }
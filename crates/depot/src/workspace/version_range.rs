@@ -0,0 +1,119 @@
+//! Version ranges in the syntax of the `engines` field, like `>=18 <20 || ^22`.
+//!
+//! Ranges are translated into [`VersionReq`]s, which differ from npm ranges mainly in
+//! separating comparators with commas and treating a bare version as a caret range.
+
+use std::{fmt, str::FromStr};
+
+use anyhow::{Context, Error, Result};
+use semver::{Version, VersionReq};
+
+/// Parses a version leniently, such that `v24.1` is `24.1.0`.
+pub fn parse_version(s: &str) -> Result<Version> {
+  let s = s.trim().trim_start_matches('v');
+  let padded = match s.split('.').count() {
+    1 => format!("{s}.0.0"),
+    2 => format!("{s}.0"),
+    _ => s.to_string(),
+  };
+  Version::parse(&padded).with_context(|| format!("Invalid version: `{s}`"))
+}
+
+#[derive(Clone, Debug)]
+pub struct VersionRange {
+  source: String,
+  alternatives: Vec<VersionReq>,
+}
+
+impl VersionRange {
+  pub fn matches(&self, version: &Version) -> bool {
+    self.alternatives.iter().any(|req| req.matches(version))
+  }
+}
+
+/// Translates a range without `||` into a [`VersionReq`].
+fn parse_comparators(range: &str) -> Result<VersionReq> {
+  let range = range.trim();
+  if let Some((low, high)) = range.split_once(" - ") {
+    return Ok(VersionReq::parse(&format!(
+      ">={}, <={}",
+      low.trim(),
+      high.trim()
+    ))?);
+  }
+
+  let mut comparators = Vec::new();
+  let mut operator = String::new();
+  for token in range.split_whitespace() {
+    let version = token.trim_start_matches(['<', '>', '=', '~', '^']);
+    if version.is_empty() {
+      // An operator separated from its version, like `>= 18`.
+      operator.push_str(token);
+      continue;
+    }
+    let mut comparator = std::mem::take(&mut operator);
+    comparator.push_str(&token[..token.len() - version.len()]);
+    let version = version.trim_start_matches('v');
+    let is_wildcard = version.contains(['x', 'X', '*']);
+    if comparator.is_empty() && !is_wildcard {
+      comparator.push('=');
+    }
+    comparator.push_str(version);
+    comparators.push(comparator);
+  }
+
+  if comparators.is_empty() {
+    return Ok(VersionReq::STAR);
+  }
+  Ok(VersionReq::parse(&comparators.join(", "))?)
+}
+
+impl FromStr for VersionRange {
+  type Err = Error;
+
+  fn from_str(s: &str) -> Result<Self> {
+    let alternatives = s
+      .split("||")
+      .map(parse_comparators)
+      .collect::<Result<Vec<_>>>()
+      .with_context(|| format!("Invalid version range: `{s}`"))?;
+    Ok(VersionRange {
+      source: s.trim().to_string(),
+      alternatives,
+    })
+  }
+}
+
+impl fmt::Display for VersionRange {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}", self.source)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_version_range() {
+    let matches = |range: &str, version: &str| {
+      let range = range.parse::<VersionRange>().unwrap();
+      range.matches(&parse_version(version).unwrap())
+    };
+
+    assert!(matches(">=18 <20 || ^22", "18.5.0"));
+    assert!(matches(">=18 <20 || ^22", "v22.1"));
+    assert!(!matches(">=18 <20 || ^22", "20.0.0"));
+    assert!(matches(">= 24", "24.0.1"));
+    assert!(matches("18.x", "18.2.0"));
+    assert!(!matches("18.x", "19.0.0"));
+    assert!(matches("10.4.1", "10.4.1"));
+    assert!(!matches("10.4.1", "10.5.0"));
+    assert!(matches("1.2.3 - 2.3", "2.3.9"));
+    assert!(!matches("1.2.3 - 2.3", "2.4.0"));
+    assert!(matches("*", "0.1.0"));
+    assert!(matches("", "0.1.0"));
+
+    assert!("not a range".parse::<VersionRange>().is_err());
+  }
+}
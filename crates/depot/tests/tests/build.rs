@@ -30,6 +30,16 @@ fn basic_lib_node() {
   assert!(p.exists("dist/lib.js.map"));
 }
 
+#[test]
+fn bundled_lib() {
+  let p = custom_project_for("lib", "browser", "--bundle");
+  p.depot("build --lint-fail");
+  assert!(p.exists("dist/lib.js"));
+  assert!(p.exists("dist/lib.cjs"));
+  assert!(p.exists("dist/lib.d.ts"));
+  assert!(p.exists("dist/lib.d.cts"));
+}
+
 #[test]
 fn basic_script_browser() {
   let p = project_for("script", "browser");
@@ -80,6 +90,15 @@ fn release() {
   assert!(!p.exists("dist/lib.js.map"));
 }
 
+#[test]
+fn profiles() {
+  let p = project();
+  p.depot("build --profile release");
+  assert!(!p.exists("dist/lib.js.map"));
+  assert!(p.maybe_depot("build --profile missing").is_err());
+  assert!(p.maybe_depot("build --release --profile dev").is_err());
+}
+
 #[test]
 fn workspace_() {
   let ws = workspace();
@@ -159,3 +178,11 @@ fn react_import() {
   p.file("src/lib.tsx", r#"import ReactDOM from "react-dom/client";"#);
   p.depot("build");
 }
+
+#[test]
+fn broken_exports() {
+  let p = project();
+  std::fs::rename(p.root().join("src/lib.ts"), p.root().join("src/index.ts")).unwrap();
+  p.depot("build");
+  assert!(p.maybe_depot("build --lint-fail").is_err());
+}
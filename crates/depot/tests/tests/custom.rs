@@ -0,0 +1,36 @@
+use depot_test_utils::{ProjectBuilder, project};
+
+fn add_codegen_command(p: &ProjectBuilder) {
+  let mut manifest: serde_json::Value = serde_json::from_str(&p.read("package.json")).unwrap();
+  manifest["depot"]["commands"] = serde_json::json!({
+    "codegen": {
+      "exec": "node",
+      "args": ["-e", "require('fs').writeFileSync('src/generated.ts', 'export let x = 1;')"],
+      "before": ["build"]
+    }
+  });
+  p.file("package.json", manifest.to_string());
+}
+
+#[test]
+fn run_directly() {
+  let p = project();
+  add_codegen_command(&p);
+  p.depot("codegen");
+  assert!(p.exists("src/generated.ts"));
+}
+
+#[test]
+fn run_before_build() {
+  let p = project();
+  add_codegen_command(&p);
+  p.depot("build");
+  assert!(p.exists("dist/generated.js"));
+}
+
+#[test]
+#[should_panic]
+fn unknown_command() {
+  let p = project();
+  p.depot("codegen");
+}
@@ -1,7 +1,10 @@
 mod build;
 mod clean;
+mod custom;
 mod doc;
 mod fix;
 mod fmt;
 mod new;
+mod run;
+mod script;
 mod test;
@@ -0,0 +1,23 @@
+use depot_test_utils::project_for;
+
+#[test]
+fn basic() {
+  let p = project_for("script", "node");
+  p.file(
+    "src/main.ts",
+    r#"
+import fs from "node:fs";
+
+fs.writeFileSync("args.txt", process.argv.slice(2).join(" "));
+  "#,
+  );
+  p.depot("run -- hello world");
+  assert_eq!(p.read("args.txt"), "hello world");
+}
+
+#[test]
+#[should_panic]
+fn not_a_script() {
+  let p = project_for("lib", "node");
+  p.depot("run");
+}
@@ -0,0 +1,52 @@
+use depot_test_utils::{project, workspace};
+
+#[test]
+fn basic() {
+  let p = project();
+  p.file(
+    "package.json",
+    r#"{
+  "name": "foo",
+  "scripts": {"generate": "node -e \"require('fs').writeFileSync('out.txt', 'ok')\""},
+  "depot": {"platform": "browser"}
+}"#,
+  );
+  p.depot("script generate");
+  assert_eq!(p.read("out.txt"), "ok");
+}
+
+#[test]
+#[should_panic]
+fn missing_script() {
+  let p = project();
+  p.depot("script generate");
+}
+
+#[test]
+fn dependency_order() {
+  let ws = workspace();
+  ws.depot("new foo");
+  ws.depot("new bar");
+
+  ws.file(
+    "packages/foo/package.json",
+    r#"{
+  "name": "foo",
+  "scripts": {"generate": "node -e \"require('fs').writeFileSync('../order.txt', 'foo')\""},
+  "depot": {"platform": "browser"}
+}"#,
+  );
+  ws.file(
+    "packages/bar/package.json",
+    r#"{
+  "name": "bar",
+  "dependencies": {"foo": "workspace:^0.1.0"},
+  "scripts": {"generate": "node -e \"require('fs').appendFileSync('../order.txt', 'bar')\""},
+  "depot": {"platform": "browser"}
+}"#,
+  );
+
+  ws.depot("init -- --no-frozen-lockfile");
+  ws.depot("script generate");
+  assert_eq!(ws.read("packages/order.txt"), "foobar");
+}